@@ -1,89 +1,677 @@
-use log::{debug, info};
-use pewcraft_common::game::{GameDefinition, GameState, GameMap, Id};
-use pewcraft_common::io::{WireCreatedGame, WireNewGameRequest, WireNewCharRequest, WireCreatedChar};
-use reqwest::{blocking::Client, Url};
+use crate::cassette::{Cassette, Interaction, Player, Recorder};
+use crate::config;
+use crate::history::{self, Exchange};
+use crate::local::LocalServer;
+use log::{debug, info, warn};
+use pewcraft_common::game::{Cell, Character, GameDefinition, GameMap, GameState, Id, Team};
+use pewcraft_common::io::{
+    WireCreatedChar, WireCreatedGame, WireNewCharRequest, WireNewGameRequest,
+};
+use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const NETWORK_FILE: &str = "network.json";
+// Sessions are stored per server URL, the local server has a fake one
+pub const LOCAL_URL: &str = "local://pewcraft";
+
+// Versions of the wire protocol (`pewcraft_common::io`) this client can speak
+pub const SUPPORTED_PROTOCOLS: RangeInclusive<u32> = 1..=1;
+// Servers predating the handshake do not know the "version" request
+const LEGACY_PROTOCOL: u32 = 1;
+
+// Answer to the "version" request
+#[derive(Debug, Deserialize)]
+pub struct WireVersion {
+    pub protocol: u32,
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+// What a character does during its turn.
+// ASSUMPTION: pewcraft_common has no wire type nor request for actions yet. This type and the
+// "<game id>/<login>/action" request are what the client expects the server to accept, nothing
+// checks them against the server: move it to pewcraft_common once the server defines it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WireAction {
+    // to a neighbouring cell
+    Move(Id<Cell>),
+    Attack(Id<Character>),
+    EndTurn,
+}
+
+// How a game goes on, as of its last action.
+// ASSUMPTION: pewcraft_common has no wire type for it yet either, this is what the client expects
+// the server to answer on "<game id>/progress".
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WireProgress {
+    // the character whose turn it is, None until every slot of the game is taken
+    pub turn: Option<Id<Character>>,
+    // characters which cannot play anymore, they still lie on their cell
+    pub defeated: Vec<Id<Character>>,
+    // the last team standing, once the game is over
+    pub winner: Option<Id<Team>>,
+}
+
+#[derive(Debug)]
+pub enum Handshake {
+    Compatible(u32),
+    // the server only speaks an older protocol
+    ServerTooOld(u32),
+    // the server only speaks a newer protocol
+    ClientTooOld(u32),
+}
+
+// Network settings, read from the "network.json" config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub connect_timeout_ms: u64,
+    pub read_timeout_ms: u64,
+    // how many times idempotent requests are retried before giving up
+    pub retries: u32,
+    // doubled after each failed attempt
+    pub initial_backoff_ms: u64,
+    // how long startup waits for the server to confirm the cached game definition
+    pub revalidate_timeout_ms: u64,
+    // how long a game refresh waits for the server, it is tried once per tick and never retried
+    pub poll_timeout_ms: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            connect_timeout_ms: 3_000,
+            read_timeout_ms: 10_000,
+            retries: 3,
+            initial_backoff_ms: 250,
+            revalidate_timeout_ms: 500,
+            poll_timeout_ms: 400,
+        }
+    }
+}
+
+impl Settings {
+    // Problems with the config file are returned so they can be reported, the defaults are used
+    pub fn load() -> (Self, Option<String>) {
+        match config::read::<Settings>(NETWORK_FILE) {
+            Ok(settings) => (settings.unwrap_or_default(), None),
+            Err(e) => (Settings::default(), Some(e)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connected,
+    // the n-th retry of a request is in progress
+    Retrying(u32),
+    Offline,
+}
+
+pub enum Error {
+    Http(reqwest::Error),
+    // the cassette has no answer for this request
+    Replay(String),
+    // the server refused the request, with its reason
+    Status(u16, String),
+    // the server answered something we cannot understand
+    Decode(String),
+    // the request was not sent, e.g. a game id typed by the user is not one
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "{}", e),
+            Error::Replay(request) => write!(f, "no recorded answer for {}", request),
+            Error::Status(status, reason) => write!(f, "{} (status {})", reason, status),
+            Error::Decode(e) => write!(f, "unexpected answer: {}", e),
+            Error::Invalid(reason) => write!(f, "invalid request: {}", reason),
+        }
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+// An answer from the server (or from a cassette)
+struct Reply {
+    status: u16,
+    etag: Option<String>,
+    body: String,
+}
+
+impl Reply {
+    fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    fn is_not_modified(&self) -> bool {
+        self.status == 304
+    }
+
+    // Err when the server refused the request
+    fn check(&self) -> Result<(), Error> {
+        if self.is_success() {
+            Ok(())
+        } else {
+            Err(Error::Status(self.status, self.refusal()))
+        }
+    }
+
+    fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        self.check()?;
+        serde_json::from_str(&self.body).map_err(|e| Error::Decode(e.to_string()))
+    }
+
+    // The reason the server gave for refusing a request
+    fn refusal(&self) -> String {
+        serde_json::from_str::<Value>(&self.body)
+            .ok()
+            .and_then(|body| body.get("error")?.as_str().map(str::to_owned))
+            .unwrap_or_else(|| "refused by the server".to_owned())
+    }
+}
+
+// The last game definition received from a server, with its ETag if the server sent one
+#[derive(Serialize, Deserialize)]
+struct CachedDefinition {
+    etag: Option<String>,
+    definition: Value,
+}
+
+impl CachedDefinition {
+    // One file per server
+    fn file(url: &str) -> String {
+        let name: String = url
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("definitions/{}.json", name)
+    }
+
+    fn load(url: &str) -> Option<Self> {
+        match config::read_cache::<CachedDefinition>(&CachedDefinition::file(url)) {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("Ignoring the cached game definition: {}", e);
+                None
+            }
+        }
+    }
+
+    // Only a definition we could decode is cached, so that the cache never holds an error
+    fn decode_and_store(url: &str, reply: &Reply) -> Result<GameDefinition, Error> {
+        let cached = CachedDefinition {
+            etag: reply.etag.clone(),
+            definition: reply.json()?,
+        };
+        let definition = cached.definition()?;
+        if let Err(e) = config::write_cache(&CachedDefinition::file(url), &cached) {
+            warn!("Could not cache the game definition: {}", e);
+        }
+        Ok(definition)
+    }
+
+    fn definition(&self) -> Result<GameDefinition, Error> {
+        serde_json::from_value(self.definition.clone()).map_err(|e| Error::Decode(e.to_string()))
+    }
+}
+
+// Told about every change of the connection status, even in the middle of a request
+pub type StatusObserver = Box<dyn Fn(ConnectionStatus) + Send + Sync>;
+
+enum Transport {
+    Http(Client),
+    Replay(Player),
+    Local(LocalServer),
+}
 
 pub struct Endpoint {
     url: Url,
-    client: Client,
+    transport: Transport,
+    recorder: Option<Recorder>,
+    settings: Settings,
+    status: Mutex<ConnectionStatus>,
+    observer: Mutex<Option<StatusObserver>>,
 }
 
 impl Endpoint {
     pub fn new<S: AsRef<str>>(url: S) -> Self {
-        info!("API endpoint: {}", url.as_ref());
+        Endpoint::with_settings(url, Settings::default())
+    }
+
+    pub fn with_settings<S: AsRef<str>>(url: S, settings: Settings) -> Self {
+        info!("API endpoint: {} ({:?})", url.as_ref(), settings);
+        let client = Client::builder()
+            .connect_timeout(Duration::from_millis(settings.connect_timeout_ms))
+            .timeout(Duration::from_millis(settings.read_timeout_ms))
+            .build()
+            .unwrap();
         Endpoint {
             url: Url::parse(url.as_ref()).unwrap(),
-            client: Client::new(),
+            transport: Transport::Http(client),
+            recorder: None,
+            settings,
+            status: Mutex::new(ConnectionStatus::Connected),
+            observer: Mutex::new(None),
         }
     }
 
-    pub fn load_game(&self) -> GameDefinition {
-        self.client
-            .get(self.url.join("game").unwrap())
-            .send()
-            .unwrap()
-            .json()
-            .unwrap()
+    // Answers every request from the cassette instead of contacting the server
+    pub fn replay<S: AsRef<str>, P: AsRef<Path>>(url: S, cassette: P) -> Result<Self, String> {
+        info!(
+            "Replaying {} as {}",
+            cassette.as_ref().display(),
+            url.as_ref()
+        );
+        Ok(Endpoint {
+            url: Url::parse(url.as_ref()).map_err(|e| e.to_string())?,
+            transport: Transport::Replay(Player::new(Cassette::load(cassette)?)),
+            recorder: None,
+            settings: Settings::default(),
+            status: Mutex::new(ConnectionStatus::Connected),
+            observer: Mutex::new(None),
+        })
+    }
+
+    // Plays against an in-process server instead of a remote one
+    pub fn local(definition: GameDefinition) -> Self {
+        Endpoint {
+            url: Url::parse(LOCAL_URL).unwrap(),
+            transport: Transport::Local(LocalServer::new(definition)),
+            recorder: None,
+            settings: Settings::default(),
+            status: Mutex::new(ConnectionStatus::Connected),
+            observer: Mutex::new(None),
+        }
+    }
+
+    // Records every request and its answer to the cassette
+    pub fn record<P: AsRef<Path>>(mut self, cassette: P) -> Self {
+        info!("Recording to {}", cassette.as_ref().display());
+        self.recorder = Some(Recorder::new(cassette.as_ref()));
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        self.url.as_str()
     }
 
-    pub fn create_game(&self, request: WireNewGameRequest) -> WireCreatedGame {
+    pub fn status(&self) -> ConnectionStatus {
+        *self.status.lock().unwrap()
+    }
+
+    // Requests block their caller, which may want to show the status before they are over:
+    // e.g. that a request is being retried
+    pub fn observe_status(&self, observer: Option<StatusObserver>) {
+        *self.observer.lock().unwrap() = observer;
+    }
+
+    fn set_status(&self, status: ConnectionStatus) {
+        let previous = std::mem::replace(&mut *self.status.lock().unwrap(), status);
+        if previous != status {
+            if let Some(observer) = &*self.observer.lock().unwrap() {
+                observer(status);
+            }
+        }
+    }
+
+    // Sends the request, keeping track of the exchange for diagnostics and recording.
+    // With `if_none_match`, the server answers 304 if its version still has this ETag.
+    // `timeout` replaces the usual ones, for the whole request.
+    fn exchange(
+        &self,
+        method: &'static str,
+        path: &str,
+        body: Option<Value>,
+        if_none_match: Option<&str>,
+        timeout: Option<Duration>,
+    ) -> Result<Reply, Error> {
+        let start = Instant::now();
+        let reply = match &self.transport {
+            Transport::Http(client) => self.request_url(path).and_then(|url| {
+                let mut request =
+                    client.request(Method::from_bytes(method.as_bytes()).unwrap(), url);
+                if let Some(body) = &body {
+                    request = request.json(body);
+                }
+                if let Some(etag) = if_none_match {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(timeout) = timeout {
+                    request = request.timeout(timeout);
+                }
+                request
+                    .send()
+                    .and_then(|response| {
+                        let status = response.status().as_u16();
+                        let etag = response
+                            .headers()
+                            .get(ETAG)
+                            .and_then(|etag| etag.to_str().ok())
+                            .map(str::to_owned);
+                        response.text().map(|body| Reply { status, etag, body })
+                    })
+                    .map_err(Error::Http)
+            }),
+            Transport::Replay(player) => match player.play(method, path, &body) {
+                Some(interaction) => Ok(Reply {
+                    status: interaction.status,
+                    etag: interaction.etag.clone(),
+                    body: interaction.response_text(),
+                }),
+                None => Err(Error::Replay(format!("{} {}", method, path))),
+            },
+            Transport::Local(server) => {
+                let (status, response) = server.handle(method, path, &body);
+                Ok(Reply {
+                    status,
+                    etag: None,
+                    body: response.to_string(),
+                })
+            }
+        };
+
+        history::record_exchange(Exchange {
+            method,
+            path: path.to_owned(),
+            status: reply.as_ref().ok().map(|r| r.status),
+            latency: start.elapsed(),
+            error: reply.as_ref().err().map(|e| e.to_string()),
+        });
+
+        if let (Some(recorder), Ok(reply)) = (&self.recorder, &reply) {
+            recorder.record(Interaction {
+                method: method.to_owned(),
+                path: path.to_owned(),
+                body,
+                status: reply.status,
+                etag: reply.etag.clone(),
+                response: serde_json::from_str(&reply.body)
+                    .unwrap_or_else(|_| Value::String(reply.body.clone())),
+            });
+        }
+
+        self.set_status(match &reply {
+            Ok(_) => ConnectionStatus::Connected,
+            Err(_) => ConnectionStatus::Offline,
+        });
+        reply
+    }
+
+    // The request must stay on our server, whatever the path holds
+    fn request_url(&self, path: &str) -> Result<Url, Error> {
+        match self.url.join(path) {
+            Ok(url) if url.origin() == self.url.origin() => Ok(url),
+            Ok(url) => Err(Error::Invalid(format!("{} is not on {}", url, self.url))),
+            Err(e) => Err(Error::Invalid(format!("{}: {}", path, e))),
+        }
+    }
+
+    // Only for idempotent requests: retries on network errors and server errors, with an
+    // exponential backoff between the attempts
+    fn exchange_with_retries(&self, method: &'static str, path: &str) -> Result<Reply, Error> {
+        let mut backoff = Duration::from_millis(self.settings.initial_backoff_ms);
+        let mut attempt = 0;
+        loop {
+            let reply = self.exchange(method, path, None, None, None);
+            let failed = match &reply {
+                Ok(reply) => reply.status >= 500,
+                // the cassette will not answer better the next time
+                Err(Error::Http(_)) => true,
+                // nor will the server, when it refused or answered something we do not understand
+                Err(Error::Replay(_))
+                | Err(Error::Status(..))
+                | Err(Error::Decode(_))
+                | Err(Error::Invalid(_)) => false,
+            };
+            if !failed || attempt == self.settings.retries {
+                return reply;
+            }
+
+            attempt += 1;
+            warn!(
+                "{} {} failed, retry {}/{} in {:?}",
+                method, path, attempt, self.settings.retries, backoff
+            );
+            self.set_status(ConnectionStatus::Retrying(attempt));
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+
+    fn get(&self, path: &str) -> Result<Reply, Error> {
+        self.exchange_with_retries("GET", path)
+    }
+
+    // A single short attempt, for what is asked again on every tick: retries would block the
+    // caller for seconds while the server is unreachable
+    fn poll(&self, path: &str) -> Result<Reply, Error> {
+        let timeout = Duration::from_millis(self.settings.poll_timeout_ms);
+        self.exchange("GET", path, None, None, Some(timeout))
+    }
+
+    fn post<T: Serialize>(&self, path: &str, body: &T) -> Result<Reply, Error> {
+        self.exchange(
+            "POST",
+            path,
+            Some(serde_json::to_value(body).unwrap()),
+            None,
+            None,
+        )
+    }
+
+    // Asks the server which protocol it speaks, before decoding any of its answers.
+    // Err when the server refuses, or answers something which is not a version.
+    pub fn handshake(&self) -> Result<Handshake, Error> {
+        let reply = self.get("version")?;
+        // older servers take "version" for the id of a game, which they do not find
+        let legacy = reply.status == 404 || (reply.is_success() && reply.body.trim() == "null");
+        let protocol = if legacy {
+            info!(
+                "The server does not know the handshake, assuming protocol {}",
+                LEGACY_PROTOCOL
+            );
+            LEGACY_PROTOCOL
+        } else {
+            let version = reply.json::<WireVersion>()?;
+            info!("Server version: {:?}", version);
+            version.protocol
+        };
+
+        Ok(if protocol < *SUPPORTED_PROTOCOLS.start() {
+            Handshake::ServerTooOld(protocol)
+        } else if protocol > *SUPPORTED_PROTOCOLS.end() {
+            Handshake::ClientTooOld(protocol)
+        } else {
+            Handshake::Compatible(protocol)
+        })
+    }
+
+    // Uses the locally cached definition when the server says it did not change, or when the
+    // server cannot be reached at all. Without a cached definition, the server must answer.
+    pub fn load_game(&self) -> Result<GameDefinition, Error> {
+        // cassettes must hold the full definition, and replay it as is; the local server already
+        // has it in memory
+        if self.recorder.is_some()
+            || matches!(self.transport, Transport::Replay(_) | Transport::Local(_))
+        {
+            return self.get("game")?.json();
+        }
+
+        let cached = CachedDefinition::load(self.url()).and_then(|cached| {
+            match cached.definition() {
+                Ok(definition) => Some((cached.etag, definition)),
+                // e.g. cached by a version of the client with other game types
+                Err(e) => {
+                    warn!("Ignoring the cached game definition: {}", e);
+                    None
+                }
+            }
+        });
+        let (etag, definition) = match cached {
+            Some(cached) => cached,
+            None => return CachedDefinition::decode_and_store(self.url(), &self.get("game")?),
+        };
+
+        // a single short attempt: we would rather start right away with the cached version
+        let timeout = Duration::from_millis(self.settings.revalidate_timeout_ms);
+        match self.exchange("GET", "game", None, etag.as_deref(), Some(timeout)) {
+            Ok(reply) if reply.is_not_modified() => {
+                info!("The cached game definition is up to date");
+                Ok(definition)
+            }
+            Ok(reply) if reply.is_success() => {
+                info!("The game definition changed, reloading it");
+                CachedDefinition::decode_and_store(self.url(), &reply).or_else(|e| {
+                    warn!(
+                        "Could not decode the new game definition ({}), using the cached one",
+                        e
+                    );
+                    Ok(definition)
+                })
+            }
+            Ok(reply) => {
+                warn!(
+                    "Unexpected answer {}, using the cached game definition",
+                    reply.status
+                );
+                Ok(definition)
+            }
+            Err(e) => {
+                warn!(
+                    "Server unreachable ({}), using the cached game definition",
+                    e
+                );
+                Ok(definition)
+            }
+        }
+    }
+
+    pub fn create_game(&self, request: WireNewGameRequest) -> Result<WireCreatedGame, Error> {
         debug!("Creating game with request: {:?}", request);
-        self.client
-            .post(self.url.join("new_game").unwrap())
-            .json(&request)
-            .send()
-            .unwrap()
-            .json()
-            .unwrap()
+        self.post("new_game", &request)?.json()
     }
 
-    pub fn join_game<S: AsRef<str>>(&self, game_id: S) -> Option<WireCreatedGame> {
-        unimplemented!()
-            /*
-        debug!("Creating char with request: {:?}", request);
-        self.client
-            .post(self.url.join(game_id.as_ref()).unwrap())
-            .json(&request)
-            .send()
-            .unwrap()
-            .json()
-            .unwrap()
-            */
+    // Joining a game only takes to know its map, characters are then created as usual.
+    // None when the game does not exist.
+    pub fn join_game<S: AsRef<str>>(&self, game_id: S) -> Result<Option<WireCreatedGame>, Error> {
+        debug!("Joining game {}", game_id.as_ref());
+        Ok(self
+            .game_state(game_id.as_ref())?
+            .map(|state| WireCreatedGame {
+                game_id: game_id.as_ref().to_owned(),
+                map: state.map,
+            }))
     }
 
-    pub fn join_game_with_char<S: AsRef<str>>(&self, game_id: S, char_id: S) -> Option<WireCreatedGame> {
-        unimplemented!()
-            /*
-        debug!("Creating char with request: {:?}", request);
-        self.client
-            .post(self.url.join(game_id.as_ref()).unwrap())
-            .json(&request)
-            .send()
-            .unwrap()
-            .json()
-            .unwrap()
-            */
+    // Comes back with a character which left the game, returns the character.
+    // Like "leave", this request is not part of pewcraft_common: the server is assumed to answer
+    // it with the same JSON as a character creation.
+    pub fn rejoin_game<S: AsRef<str>>(&self, game_id: S, login: S) -> Result<Id<Character>, Error> {
+        debug!("Rejoining game {} as {}", game_id.as_ref(), login.as_ref());
+        let path = request_path(&[game_id.as_ref(), login.as_ref()])?;
+        let WireCreatedChar(_, id) = self.exchange("POST", &path, None, None, None)?.json()?;
+        Ok(id)
     }
 
-    pub fn create_char<S: AsRef<str>>(&self, game_id: S, request: WireNewCharRequest) -> WireCreatedChar {
+    // Err with the reason when the server refused the character, e.g. because its slot is taken
+    pub fn create_char<S: AsRef<str>>(
+        &self,
+        game_id: S,
+        request: WireNewCharRequest,
+    ) -> Result<WireCreatedChar, Error> {
         debug!("Creating char with request: {:?}", request);
-        self.client
-            .post(self.url.join(game_id.as_ref()).unwrap())
-            .json(&request)
-            .send()
-            .unwrap()
+        self.post(&request_path(&[game_id.as_ref()])?, &request)?
             .json()
-            .unwrap()
     }
 
-    pub fn game_state<S: AsRef<str>>(&self, game_id: S) -> Option<GameState> {
-        self.client
-            .get(self.url.join(game_id.as_ref()).unwrap())
-            .send()
-            .unwrap()
+    // Err with the reason when the action was refused, e.g. because it is not our turn.
+    // The request is an assumption, see WireAction.
+    pub fn act<S: AsRef<str>>(
+        &self,
+        game_id: S,
+        login: S,
+        action: &WireAction,
+    ) -> Result<(), Error> {
+        debug!("{} plays {:?}", login.as_ref(), action);
+        let path = request_path(&[game_id.as_ref(), login.as_ref(), "action"])?;
+        self.post(&path, action)?.check()
+    }
+
+    // Tells the server we are leaving the game, our character can be resumed later.
+    // Leaving must never prevent the user from quitting, so failures are only logged.
+    pub fn leave_game<S: AsRef<str>>(&self, game_id: S, login: S) -> bool {
+        debug!("Leaving game {} as {}", game_id.as_ref(), login.as_ref());
+        let path = request_path(&[game_id.as_ref(), login.as_ref(), "leave"]);
+        match path.and_then(|path| self.exchange("POST", &path, None, None, None)) {
+            Ok(reply) if reply.is_success() => true,
+            Ok(reply) => {
+                warn!("Server refused to let us leave: {}", reply.status);
+                false
+            }
+            Err(e) => {
+                warn!("Could not leave the game: {}", e);
+                false
+            }
+        }
+    }
+
+    pub fn progress<S: AsRef<str>>(&self, game_id: S) -> Result<WireProgress, Error> {
+        self.get(&request_path(&[game_id.as_ref(), "progress"])?)?
             .json()
-            .unwrap()
+    }
+
+    // None when the game does not exist
+    pub fn game_state<S: AsRef<str>>(&self, game_id: S) -> Result<Option<GameState>, Error> {
+        Endpoint::decode_game_state(self.get(&request_path(&[game_id.as_ref()])?)?)
+    }
+
+    // The progress and the state of the game, as the screen refreshes them on every tick.
+    // Only one short attempt is made, see `poll`.
+    pub fn poll_game<S: AsRef<str>>(
+        &self,
+        game_id: S,
+    ) -> Result<(WireProgress, Option<GameState>), Error> {
+        let progress = self
+            .poll(&request_path(&[game_id.as_ref(), "progress"])?)?
+            .json()?;
+        let state = Endpoint::decode_game_state(self.poll(&request_path(&[game_id.as_ref()])?)?)?;
+        Ok((progress, state))
+    }
+
+    fn decode_game_state(reply: Reply) -> Result<Option<GameState>, Error> {
+        if reply.status == 404 {
+            return Ok(None);
+        }
+        reply.json()
+    }
+}
+
+// Game ids and logins are typed by the user and end up in the path of requests: anything but
+// letters and digits could change the request, e.g. "../new_game" or "//another.host/"
+fn request_path(segments: &[&str]) -> Result<String, Error> {
+    match segments
+        .iter()
+        .find(|s| s.is_empty() || !s.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        Some(segment) => Err(Error::Invalid(format!("'{}' is not a valid id", segment))),
+        None => Ok(segments.join("/")),
     }
 }
 
@@ -92,3 +680,101 @@ impl fmt::Debug for Endpoint {
         write!(f, "Endpoint {{ url: {:?}, client: <hidden> }}", self.url)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/session.cassette"
+    );
+
+    fn request(name: &str, class: usize, team: usize, position: usize) -> WireNewCharRequest {
+        WireNewCharRequest {
+            name: name.to_owned(),
+            class: Id::new(class),
+            team: Id::new(team),
+            position: Id::new(position),
+        }
+    }
+
+    #[test]
+    fn replays_a_recorded_session() {
+        let endpoint = Endpoint::replay("http://localhost:8000/", SESSION).unwrap();
+        assert!(matches!(endpoint.handshake(), Ok(Handshake::Compatible(1))));
+        let game = endpoint.load_game().unwrap();
+        assert_eq!(game.maps.ids().len(), 1);
+
+        let created = endpoint
+            .create_game(WireNewGameRequest {
+                map: Id::new(0),
+                team_size: 1,
+            })
+            .unwrap();
+        let game_id = created.game_id.as_str();
+        let WireCreatedChar(login, id) = endpoint
+            .create_char(game_id, request("Alice", 0, 0, 0))
+            .unwrap();
+        assert_eq!((login.as_str(), id), ("a8c2lq0w7e", Id::new(0)));
+        let state = endpoint.game_state(game_id).unwrap().unwrap();
+        assert_eq!(state.characters.iter().count(), 1);
+        assert_eq!(endpoint.progress(game_id).unwrap().turn, None);
+
+        let WireCreatedChar(bob, _) = endpoint
+            .create_char(game_id, request("Bob", 1, 1, 5))
+            .unwrap();
+        let state = endpoint.game_state(game_id).unwrap().unwrap();
+        assert_eq!(state.characters.iter().count(), 2);
+        assert_eq!(endpoint.progress(game_id).unwrap().turn, Some(Id::new(0)));
+
+        // only the refusal is recorded for Bob's action
+        let refused = endpoint.act(game_id, bob.as_str(), &WireAction::EndTurn);
+        assert!(matches!(refused, Err(Error::Status(409, _))));
+        assert!(endpoint.game_state("nosuchgame").unwrap().is_none());
+    }
+
+    #[test]
+    fn replay_fails_on_requests_it_has_no_answer_for() {
+        let endpoint = Endpoint::replay("http://localhost:8000/", SESSION).unwrap();
+        let unknown = endpoint.act("k3v9x0q2mz", "a8c2lq0w7e", &WireAction::EndTurn);
+        assert!(matches!(unknown, Err(Error::Replay(_))));
+    }
+
+    #[test]
+    fn ids_which_would_change_the_request_are_refused() {
+        assert_eq!(
+            request_path(&["k3v9x0q2mz", "progress"]).unwrap(),
+            "k3v9x0q2mz/progress"
+        );
+        for id in &[
+            "",
+            "/",
+            "//evil.host/x",
+            "../new_game",
+            "a b",
+            "k3v9?x=1",
+            "é",
+        ] {
+            assert!(
+                matches!(request_path(&[*id]), Err(Error::Invalid(_))),
+                "{:?}",
+                id
+            );
+        }
+        // nothing is asked to the server, nor to the cassette
+        let endpoint = Endpoint::replay("http://localhost:8000/", SESSION).unwrap();
+        let invalid = endpoint.game_state("//evil.host/x");
+        assert!(matches!(invalid, Err(Error::Invalid(_))));
+    }
+
+    #[test]
+    fn requests_stay_on_the_server() {
+        let endpoint = Endpoint::new("http://localhost:8000/");
+        assert!(endpoint.request_url("abc/progress").is_ok());
+        assert!(matches!(
+            endpoint.request_url("//evil.host/x"),
+            Err(Error::Invalid(_))
+        ));
+    }
+}
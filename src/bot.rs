@@ -0,0 +1,319 @@
+use crate::api::{Endpoint, Error, WireAction};
+use crate::config;
+use crate::grid::{distance, neighbours};
+use crate::rules::{occupant, walkable};
+use log::{debug, info, warn};
+use pewcraft_common::game::{Cell, Character, GameDefinition, GameMap, GameState, Id, Team};
+use pewcraft_common::io::{WireCreatedChar, WireNewCharRequest};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BOT_FILE: &str = "bot.json";
+// How often the bots of a pool check whether it is their turn
+const PLAY_INTERVAL: Duration = Duration::from_millis(500);
+
+// A computer-controlled character, to fill the slots of a game no human takes.
+// It plays through the Endpoint like any client, so the server cannot tell it apart from a player.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    // Chances out of 100 to play a random move instead of the best one
+    fn mistakes(self) -> u64 {
+        match self {
+            Difficulty::Easy => 40,
+            Difficulty::Normal => 15,
+            Difficulty::Hard => 0,
+        }
+    }
+}
+
+// Content of the "bot.json" config file
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            difficulty: Difficulty::Normal,
+        }
+    }
+}
+
+impl Settings {
+    // Problems with the config file are returned so they can be reported, the defaults are used
+    pub fn load() -> (Self, Option<String>) {
+        match config::read::<Settings>(BOT_FILE) {
+            Ok(settings) => (settings.unwrap_or_default(), None),
+            Err(e) => (Settings::default(), Some(e)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Bot<'a> {
+    endpoint: &'a Endpoint,
+    map: &'a GameMap,
+    game_id: String,
+    login: String,
+    id: Id<Character>,
+    team: Id<Team>,
+    difficulty: Difficulty,
+    rng: u64,
+}
+
+impl<'a> Bot<'a> {
+    // Creates a character in the first team with a free spawn position
+    pub fn join(
+        endpoint: &'a Endpoint,
+        game: &'a GameDefinition,
+        game_id: &str,
+        difficulty: Difficulty,
+    ) -> Result<Self, String> {
+        let state = endpoint
+            .game_state(game_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("no game {}", game_id))?;
+        let map = game
+            .maps
+            .get(state.map)
+            .ok_or_else(|| format!("unknown map {}", state.map.raw()))?;
+        let classes = game.classes.ids();
+        if classes.is_empty() {
+            return Err("there is no class to play".to_owned());
+        }
+        let mut rng = seed();
+
+        for (team_index, (_, spawns)) in map.teams.iter().enumerate() {
+            for position in spawns {
+                if occupant(&state, *position).is_some() {
+                    continue;
+                }
+
+                let team = Id::new(team_index);
+                let class = classes[next(&mut rng) as usize % classes.len()];
+                let request = WireNewCharRequest {
+                    name: format!("Bot {}", state.characters.iter().count() + 1),
+                    class,
+                    team,
+                    position: *position,
+                };
+                match endpoint.create_char(game_id, request) {
+                    Ok(WireCreatedChar(login, id)) => {
+                        info!("Bot {} joined game {} ({:?})", login, game_id, difficulty);
+                        return Ok(Bot {
+                            endpoint,
+                            map,
+                            game_id: game_id.to_owned(),
+                            login,
+                            id,
+                            team,
+                            difficulty,
+                            rng,
+                        });
+                    }
+                    // most likely a full team, the next one may have room
+                    Err(e) => debug!("Bot refused in team {}: {}", team_index, e),
+                }
+            }
+        }
+
+        Err(format!("no free slot in game {}", game_id))
+    }
+
+    pub fn login(&self) -> &str {
+        &self.login
+    }
+
+    // Plays a whole turn if it is ours.
+    // Meant to be called regularly, e.g. on every tick of the client, until the game is over.
+    pub fn play(&mut self) -> Turn {
+        let state = match self.endpoint.game_state(&self.game_id) {
+            Ok(Some(state)) => state,
+            Ok(None) => {
+                warn!("Game {} does not exist anymore", self.game_id);
+                return Turn::Over;
+            }
+            Err(e) => {
+                debug!("Bot {} cannot see the game: {}", self.login, e);
+                return Turn::Waiting;
+            }
+        };
+        let position = match state.characters.get(self.id) {
+            Some(me) => me.position,
+            None => {
+                warn!(
+                    "Bot {} has no character in game {}",
+                    self.login, self.game_id
+                );
+                return Turn::Over;
+            }
+        };
+
+        // without the progress of the game, the server tells us when it is not our turn
+        let defeated = match self.endpoint.progress(&self.game_id) {
+            Ok(progress) => {
+                if progress.winner.is_some() || progress.defeated.contains(&self.id) {
+                    info!("Bot {} is done with game {}", self.login, self.game_id);
+                    return Turn::Over;
+                }
+                if progress.turn != Some(self.id) {
+                    return Turn::Waiting;
+                }
+                progress.defeated
+            }
+            Err(e) => {
+                debug!(
+                    "Bot {} cannot see the progress of the game: {}",
+                    self.login, e
+                );
+                Vec::new()
+            }
+        };
+
+        let action = self.choose(&state, &defeated, position);
+        let ends_turn = matches!(action, WireAction::EndTurn);
+        if let Err(e) = self.act(&action) {
+            debug!("Bot {} waits: {}", self.login, e);
+            return Turn::Waiting;
+        }
+        if !ends_turn {
+            if let Err(e) = self.act(&WireAction::EndTurn) {
+                warn!("Bot {} could not end its turn: {}", self.login, e);
+            }
+        }
+        Turn::Played
+    }
+
+    fn act(&self, action: &WireAction) -> Result<(), Error> {
+        self.endpoint
+            .act(self.game_id.as_str(), self.login.as_str(), action)
+    }
+
+    // Attacks an enemy in reach, otherwise moves toward the nearest one.
+    // Skills are not part of the game definition yet: the basic attack is the best one we know.
+    fn choose(
+        &mut self,
+        state: &GameState,
+        defeated: &[Id<Character>],
+        position: Id<Cell>,
+    ) -> WireAction {
+        let enemies: Vec<(Id<Character>, Id<Cell>)> = state
+            .characters
+            .iter()
+            .filter(|(id, c)| c.team != self.team && !defeated.contains(id))
+            .map(|(id, c)| (id, c.position))
+            .collect();
+
+        if let Some((target, _)) = enemies
+            .iter()
+            .find(|(_, cell)| distance(self.map, position, *cell) == 1)
+        {
+            return WireAction::Attack(*target);
+        }
+
+        let free: Vec<Id<Cell>> = neighbours(self.map, position)
+            .into_iter()
+            .filter(|cell| walkable(self.map, *cell) && occupant(state, *cell).is_none())
+            .collect();
+        if free.is_empty() {
+            return WireAction::EndTurn;
+        }
+        if next(&mut self.rng) % 100 < self.difficulty.mistakes() {
+            return WireAction::Move(free[next(&mut self.rng) as usize % free.len()]);
+        }
+
+        let nearest = enemies
+            .iter()
+            .min_by_key(|(_, cell)| distance(self.map, position, *cell));
+        match nearest {
+            Some((_, enemy)) => {
+                let best = free
+                    .iter()
+                    .min_by_key(|cell| distance(self.map, **cell, *enemy))
+                    .unwrap();
+                WireAction::Move(*best)
+            }
+            None => WireAction::EndTurn,
+        }
+    }
+}
+
+// What came of asking a bot to play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    // it is not its turn, or the server could not be reached
+    Waiting,
+    Played,
+    // the game is won or the bot is defeated, there is nothing left for it to play
+    Over,
+}
+
+// The bots added from the client, playing on a thread of their own: their requests block, the
+// client must not wait for them.
+// `run` plays them until `stop` is called, e.g. in a scoped thread.
+#[derive(Debug, Default)]
+pub struct Pool<'a> {
+    // bots added since the last round, `run` owns the others
+    added: Mutex<Vec<Bot<'a>>>,
+    stopped: AtomicBool,
+}
+
+impl<'a> Pool<'a> {
+    pub fn new() -> Self {
+        Pool::default()
+    }
+
+    pub fn add(&self, bot: Bot<'a>) {
+        self.added.lock().unwrap().push(bot);
+    }
+
+    pub fn run(&self) {
+        let mut bots = Vec::new();
+        while !self.stopped.load(Ordering::Relaxed) {
+            bots.append(&mut self.added.lock().unwrap());
+            bots.retain_mut(|bot| bot.play() != Turn::Over);
+            thread::sleep(PLAY_INTERVAL);
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+// A small xorshift generator: bots only need to look unpredictable
+fn seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos() as u64);
+    nanos | 1
+}
+
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
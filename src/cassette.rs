@@ -0,0 +1,267 @@
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+// A "cassette" is the list of every HTTP request the client made, with the server's answer.
+// Recording one while playing lets us replay the exact same session offline, e.g. to reproduce
+// a bug report, and the recorded answers double as fixtures for the wire types (see
+// tests/fixtures).
+// The file holds one interaction per line, as JSON.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    // the answer, as JSON when it was valid JSON, as a string otherwise
+    pub response: Value,
+}
+
+impl Interaction {
+    // The raw body of the answer, as it was received
+    pub fn response_text(&self) -> String {
+        match &self.response {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    fn matches(&self, method: &str, path: &str, body: &Option<Value>) -> bool {
+        self.method == method && self.path == path && &self.body == body
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Cassette::parse(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let interactions = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| format!("line {}: {}", index + 1, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Cassette { interactions })
+    }
+
+    // Decodes the answer to the first matching request, to use a cassette as a fixture,
+    // e.g. decode::<GameDefinition>("GET", "game")
+    #[cfg(test)]
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+    ) -> Option<Result<T, String>> {
+        self.interactions
+            .iter()
+            .find(|i| i.method == method && i.path == path)
+            .map(|i| serde_json::from_value(i.response.clone()).map_err(|e| e.to_string()))
+    }
+}
+
+// Appends every interaction to a cassette file, as soon as it happens so that a crash does not
+// lose the recording. A previous recording at the same path is replaced.
+#[derive(Debug)]
+pub struct Recorder {
+    // None when the file could not be created, the session is then not recorded
+    file: Mutex<Option<File>>,
+}
+
+impl Recorder {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(|e| warn!("Could not create the cassette {}: {}", path.display(), e))
+            .ok();
+        Recorder {
+            file: Mutex::new(file),
+        }
+    }
+
+    pub fn record(&self, interaction: Interaction) {
+        let mut file = self.file.lock().unwrap();
+        let file = match file.as_mut() {
+            Some(file) => file,
+            None => return,
+        };
+        let line = serde_json::to_string(&interaction).unwrap();
+        if let Err(e) = writeln!(file, "{}", line) {
+            warn!("Could not write the cassette: {}", e);
+        }
+    }
+}
+
+// Serves the answers of a cassette instead of contacting a server.
+// Interactions are served in order: the same request twice (e.g. polling the game state) gets
+// the successive recorded answers, then the last one forever.
+#[derive(Debug)]
+pub struct Player {
+    interactions: Vec<Interaction>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl Player {
+    pub fn new(cassette: Cassette) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        Player {
+            interactions: cassette.interactions,
+            used: Mutex::new(used),
+        }
+    }
+
+    pub fn play(&self, method: &str, path: &str, body: &Option<Value>) -> Option<&Interaction> {
+        let mut used = self.used.lock().unwrap();
+        let unused = self
+            .interactions
+            .iter()
+            .enumerate()
+            .find(|(index, i)| !used[*index] && i.matches(method, path, body));
+
+        match unused {
+            Some((index, interaction)) => {
+                used[index] = true;
+                Some(interaction)
+            }
+            None => {
+                debug!(
+                    "No unused answer for {} {}, replaying the last one",
+                    method, path
+                );
+                self.interactions
+                    .iter()
+                    .rev()
+                    .find(|i| i.matches(method, path, body))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pewcraft_common::game::{GameDefinition, GameState, Id};
+    use pewcraft_common::io::{WireCreatedChar, WireCreatedGame};
+    use std::env;
+    use std::process;
+
+    const SESSION: &str = include_str!("../tests/fixtures/session.cassette");
+
+    fn session() -> Cassette {
+        Cassette::parse(SESSION).unwrap()
+    }
+
+    fn interaction(path: &str, response: Value) -> Interaction {
+        Interaction {
+            method: "GET".to_owned(),
+            path: path.to_owned(),
+            body: None,
+            status: 200,
+            etag: None,
+            response,
+        }
+    }
+
+    #[test]
+    fn parses_one_interaction_per_line() {
+        assert_eq!(session().interactions.len(), SESSION.lines().count());
+        assert!(Cassette::parse("").unwrap().interactions.is_empty());
+        let e = Cassette::parse("{\"method\":\"GET\"}\n").unwrap_err();
+        assert!(e.starts_with("line 1:"), "{}", e);
+    }
+
+    #[test]
+    fn decodes_the_game_definition() {
+        let definition: GameDefinition = session().decode("GET", "game").unwrap().unwrap();
+        let map = definition.maps.get(Id::new(0)).unwrap();
+        assert_eq!(map.name, "Duel");
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(map.data.len(), 6);
+        assert_eq!(map.teams.len(), 2);
+        let class = definition.classes.get(Id::new(1)).unwrap();
+        assert_eq!(class.name, "Archer");
+    }
+
+    #[test]
+    fn decodes_the_game_state() {
+        let state: GameState = session().decode("GET", "k3v9x0q2mz").unwrap().unwrap();
+        assert_eq!(state.map, Id::new(0));
+        let alice = state.characters.get(Id::new(0)).unwrap();
+        assert_eq!(alice.name, "Alice");
+        assert_eq!(alice.position, Id::new(0));
+    }
+
+    #[test]
+    fn decodes_created_games_and_characters() {
+        let game: WireCreatedGame = session().decode("POST", "new_game").unwrap().unwrap();
+        assert_eq!(game.game_id, "k3v9x0q2mz");
+        let WireCreatedChar(login, id) = session().decode("POST", "k3v9x0q2mz").unwrap().unwrap();
+        assert_eq!(login, "a8c2lq0w7e");
+        assert_eq!(id, Id::new(0));
+    }
+
+    #[test]
+    fn decode_reports_unknown_requests_and_wrong_types() {
+        assert!(session().decode::<GameState>("GET", "unknown").is_none());
+        assert!(session()
+            .decode::<GameState>("GET", "version")
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn replays_answers_in_order_then_the_last_one() {
+        let player = Player::new(Cassette {
+            interactions: vec![
+                interaction("game", Value::from(1)),
+                interaction("other", Value::from(0)),
+                interaction("game", Value::from(2)),
+            ],
+        });
+        let answer = || player.play("GET", "game", &None).unwrap().response.clone();
+        assert_eq!(answer(), Value::from(1));
+        assert_eq!(answer(), Value::from(2));
+        assert_eq!(answer(), Value::from(2));
+        assert!(player.play("POST", "game", &None).is_none());
+    }
+
+    #[test]
+    fn recorder_appends_to_a_new_cassette() {
+        let path = env::temp_dir().join(format!("pewcraft-recorder-{}.cassette", process::id()));
+        fs::write(&path, "a previous recording\n").unwrap();
+
+        let recorder = Recorder::new(&path);
+        recorder.record(interaction("game", Value::from(1)));
+        recorder.record(interaction("k3v9x0q2mz", Value::from("text")));
+        let cassette = Cassette::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let interactions = cassette.unwrap().interactions;
+        assert_eq!(interactions.len(), 2);
+        assert_eq!(interactions[0].path, "game");
+        assert_eq!(interactions[1].response_text(), "text");
+    }
+}
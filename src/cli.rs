@@ -0,0 +1,478 @@
+use crate::json_lines;
+use crate::tui::GlyphSet;
+use pewcraft_common::game::{GameDefinition, Id};
+use pewcraft_common::io::WireNewGameRequest;
+use pewcraft_frontend::api::Endpoint;
+use pewcraft_frontend::bot::{self, Bot, Difficulty, Turn};
+use pewcraft_frontend::validate;
+use serde_json::json;
+use std::env;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const USAGE: &str = r#"Usage: pewcraft_frontend [OPTIONS] [COMMAND]
+
+Commands:
+    play (default)                          start the game
+    check                                   validate the game definition sent by the server
+    maps                                    list the maps
+    classes                                 list the classes
+    game <ID>                               show the state of a game
+    create-game --map <ID> --team-size <N>  create a game and print its id
+    bot <GAME_ID> [--bots <N>] [--difficulty <easy|normal|hard>]
+                                            fill slots of a game with bots, until it is over
+    json-lines                              play through JSON commands on stdin, see below
+
+Options:
+    --server <URL>    server to connect to (default: http://localhost:8000)
+    --record <FILE>   record every request and answer to the file
+    --replay <FILE>   answer every request from a recorded file instead of the server
+    --local <FILE>    play offline, against a local server using the game definition in the file
+    --json            print the output of the commands as JSON
+    --plain           play with a line-based interface instead of the full screen one
+                      (the default when TERM is 'dumb')
+    --glyphs <SET>    characters to draw the map and panels with: double, single, rounded or
+                      ascii (default: from theme.json, or detected from the locale)
+
+JSON lines:
+    One command per line on stdin, each answered by exactly one message per line on stdout.
+    {"command": "definition"}
+        -> {"type": "definition", "definition": <game definition>}
+    {"command": "create_game", "map": 0, "team_size": 1}
+        -> {"type": "game", "game_id": "...", "map": 0}
+    {"command": "join", "game_id": "...", "name": "Bob", "class": 0, "team": 0,
+     "position": 3}
+        -> a state message, as for the state command
+    {"command": "state"}
+        -> {"type": "state", "game_id": "...", "login": "...", "character": 0,
+            "state": <game state>,
+            "progress": {"turn": 0, "defeated": [], "winner": null}, "legal_actions": [...]}
+           progress is null when the server could not tell it, legal_actions is empty unless
+           it is our turn
+    {"command": "act", "action": {"Move": 4} | {"Attack": 1} | "EndTurn"}
+        -> {"type": "ok"}
+    {"command": "quit"}
+        -> no message, the program exits
+    A command which fails or is not understood gets {"type": "error", "message": "..."}.
+    The state and act commands need a game joined first."#;
+
+#[derive(Debug)]
+pub enum Command {
+    Play,
+    Check,
+    Maps,
+    Classes,
+    Game(String),
+    CreateGame {
+        map: usize,
+        team_size: usize,
+    },
+    JsonLines,
+    Bot {
+        game_id: String,
+        bots: usize,
+        difficulty: Option<Difficulty>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub server: String,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub local: Option<String>,
+    pub json: bool,
+    pub plain: bool,
+    pub glyphs: Option<GlyphSet>,
+    pub command: Command,
+}
+
+pub fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn value<I: Iterator<Item = String>>(args: &mut I) -> String {
+    args.next().unwrap_or_else(|| usage())
+}
+
+fn number<I: Iterator<Item = String>>(args: &mut I) -> usize {
+    value(args).parse().unwrap_or_else(|_| usage())
+}
+
+pub fn parse() -> Options {
+    let mut options = Options {
+        server: "http://localhost:8000".to_owned(),
+        record: None,
+        replay: None,
+        local: None,
+        json: false,
+        plain: env::var("TERM").map_or(false, |term| term == "dumb"),
+        glyphs: None,
+        command: Command::Play,
+    };
+    let mut map = None;
+    let mut team_size = None;
+    let mut create_game = false;
+    let mut bots = 1;
+    let mut difficulty = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--server" => options.server = value(&mut args),
+            "--record" => options.record = Some(value(&mut args)),
+            "--replay" => options.replay = Some(value(&mut args)),
+            "--local" => options.local = Some(value(&mut args)),
+            "--json" => options.json = true,
+            "--plain" => options.plain = true,
+            "--glyphs" => {
+                options.glyphs =
+                    Some(GlyphSet::from_name(&value(&mut args)).unwrap_or_else(|| usage()))
+            }
+            "--map" => map = Some(number(&mut args)),
+            "--team-size" => team_size = Some(number(&mut args)),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "play" => options.command = Command::Play,
+            "check" => options.command = Command::Check,
+            "maps" => options.command = Command::Maps,
+            "classes" => options.command = Command::Classes,
+            "game" => options.command = Command::Game(value(&mut args)),
+            "create-game" => create_game = true,
+            "json-lines" => options.command = Command::JsonLines,
+            "bot" => {
+                options.command = Command::Bot {
+                    game_id: value(&mut args),
+                    bots: 1,
+                    difficulty: None,
+                }
+            }
+            "--bots" => bots = number(&mut args),
+            "--difficulty" => {
+                difficulty =
+                    Some(Difficulty::from_name(&value(&mut args)).unwrap_or_else(|| usage()))
+            }
+            _ => usage(),
+        }
+    }
+
+    if options.replay.is_some() && options.local.is_some() {
+        usage();
+    }
+    if let Command::Bot {
+        bots: count,
+        difficulty: level,
+        ..
+    } = &mut options.command
+    {
+        *count = bots;
+        *level = difficulty;
+    }
+    if create_game {
+        options.command = match (map, team_size) {
+            (Some(map), Some(team_size)) => Command::CreateGame { map, team_size },
+            _ => usage(),
+        };
+    }
+    options
+}
+
+// Runs a non-interactive command, returns the exit code
+pub fn run(command: &Command, endpoint: &Endpoint, game: &GameDefinition, json: bool) -> i32 {
+    match command {
+        Command::Play => unreachable!("the game is not a command line command"),
+        Command::Check => check(game, json),
+        Command::Maps => {
+            maps(game, json);
+            0
+        }
+        Command::Classes => {
+            classes(game, json);
+            0
+        }
+        Command::Game(game_id) => show_game(endpoint, game, game_id, json),
+        Command::CreateGame { map, team_size } => {
+            create_game(endpoint, game, *map, *team_size, json)
+        }
+        Command::JsonLines => json_lines::run(endpoint, game),
+        Command::Bot {
+            game_id,
+            bots,
+            difficulty,
+        } => run_bots(endpoint, game, game_id, *bots, *difficulty),
+    }
+}
+
+fn print_json(value: &serde_json::Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
+
+fn check(game: &GameDefinition, json: bool) -> i32 {
+    let problems = validate::validate(game);
+    if json {
+        print_json(&json!(problems
+            .iter()
+            .map(|p| json!({ "location": p.location, "message": p.message }))
+            .collect::<Vec<_>>()));
+    } else {
+        for problem in &problems {
+            println!("{}", problem);
+        }
+        println!("{} problem(s) found in the game definition", problems.len());
+    }
+
+    if problems.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+fn maps(game: &GameDefinition, json: bool) {
+    let maps: Vec<_> = game
+        .maps
+        .ids()
+        .into_iter()
+        .filter_map(|id| game.maps.get(id).map(|map| (id, map)))
+        .collect();
+
+    if json {
+        print_json(&json!(maps
+            .iter()
+            .map(|(id, map)| json!({
+                "id": id.raw(),
+                "name": map.name,
+                "width": map.width,
+                "height": map.height,
+                "teams": map.teams.len(),
+            }))
+            .collect::<Vec<_>>()));
+        return;
+    }
+
+    let name_width = maps
+        .iter()
+        .map(|(_, m)| m.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:>4}  {:name_width$}  {:>5}  {:>6}  {:>5}",
+        "ID",
+        "NAME",
+        "WIDTH",
+        "HEIGHT",
+        "TEAMS",
+        name_width = name_width
+    );
+    for (id, map) in maps {
+        println!(
+            "{:>4}  {:name_width$}  {:>5}  {:>6}  {:>5}",
+            id.raw(),
+            map.name,
+            map.width,
+            map.height,
+            map.teams.len(),
+            name_width = name_width
+        );
+    }
+}
+
+fn classes(game: &GameDefinition, json: bool) {
+    let classes: Vec<_> = game
+        .classes
+        .ids()
+        .into_iter()
+        .filter_map(|id| game.classes.get(id).map(|class| (id, class)))
+        .collect();
+
+    if json {
+        print_json(&json!(classes
+            .iter()
+            .map(|(id, class)| json!({
+                "id": id.raw(),
+                "name": class.name,
+                "description": class.description,
+            }))
+            .collect::<Vec<_>>()));
+        return;
+    }
+
+    let name_width = classes
+        .iter()
+        .map(|(_, c)| c.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:>4}  {:name_width$}  {}",
+        "ID",
+        "NAME",
+        "DESCRIPTION",
+        name_width = name_width
+    );
+    for (id, class) in classes {
+        println!(
+            "{:>4}  {:name_width$}  {}",
+            id.raw(),
+            class.name,
+            class.description,
+            name_width = name_width
+        );
+    }
+}
+
+fn show_game(endpoint: &Endpoint, game: &GameDefinition, game_id: &str, json: bool) -> i32 {
+    let state = match endpoint.game_state(game_id) {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            eprintln!("No game with id {}", game_id);
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("Could not get the state of game {}: {}", game_id, e);
+            return 1;
+        }
+    };
+
+    if json {
+        print_json(&serde_json::to_value(&state).unwrap());
+        return 0;
+    }
+
+    let map = game.maps.get(state.map);
+    let map_name = map.map_or("<unknown>", |map| map.name.as_str());
+    println!("Game {} on map '{}'", game_id, map_name);
+
+    let rows: Vec<_> = state
+        .characters
+        .iter()
+        .map(|(id, c)| {
+            let class = game
+                .classes
+                .get(c.class)
+                .map_or("?", |class| class.name.as_str());
+            let team = map
+                .and_then(|map| map.teams.get(c.team.raw()))
+                .map_or("?", |(name, _)| name.as_str());
+            let position = match map {
+                Some(map) => {
+                    let (x, y) = map.id_to_xy(c.position);
+                    format!("({},{})", x, y)
+                }
+                None => "?".to_owned(),
+            };
+            (id, &c.name, class, team, position)
+        })
+        .collect();
+    if rows.is_empty() {
+        println!("No character has joined yet");
+        return 0;
+    }
+
+    let name_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(4);
+    let class_width = rows.iter().map(|r| r.2.len()).max().unwrap_or(0).max(5);
+    let team_width = rows.iter().map(|r| r.3.len()).max().unwrap_or(0).max(4);
+    println!(
+        "{:>4}  {:nw$}  {:cw$}  {:tw$}  {}",
+        "ID",
+        "NAME",
+        "CLASS",
+        "TEAM",
+        "POSITION",
+        nw = name_width,
+        cw = class_width,
+        tw = team_width
+    );
+    for (id, name, class, team, position) in rows {
+        println!(
+            "{:>4}  {:nw$}  {:cw$}  {:tw$}  {}",
+            id.raw(),
+            name,
+            class,
+            team,
+            position,
+            nw = name_width,
+            cw = class_width,
+            tw = team_width
+        );
+    }
+    0
+}
+
+fn create_game(
+    endpoint: &Endpoint,
+    game: &GameDefinition,
+    map: usize,
+    team_size: usize,
+    json: bool,
+) -> i32 {
+    if game.maps.get(Id::new(map)).is_none() {
+        eprintln!("No map with id {}, see the 'maps' command", map);
+        return 1;
+    }
+
+    let request = WireNewGameRequest {
+        map: Id::new(map),
+        team_size,
+    };
+    let created = match endpoint.create_game(request) {
+        Ok(created) => created,
+        Err(e) => {
+            eprintln!("Could not create the game: {}", e);
+            return 1;
+        }
+    };
+    if json {
+        print_json(&serde_json::to_value(&created).unwrap());
+    } else {
+        println!("{}", created.game_id);
+    }
+    0
+}
+
+fn run_bots(
+    endpoint: &Endpoint,
+    game: &GameDefinition,
+    game_id: &str,
+    count: usize,
+    difficulty: Option<Difficulty>,
+) -> i32 {
+    let difficulty = difficulty.unwrap_or_else(|| {
+        let (settings, problem) = bot::Settings::load();
+        if let Some(problem) = problem {
+            eprintln!("Bot settings: {}", problem);
+        }
+        settings.difficulty
+    });
+
+    let mut bots = Vec::new();
+    for _ in 0..count {
+        match Bot::join(endpoint, game, game_id, difficulty) {
+            Ok(bot) => {
+                println!("{}/{}", game_id, bot.login());
+                bots.push(bot);
+            }
+            Err(e) => {
+                eprintln!("Could not add a bot: {}", e);
+                break;
+            }
+        }
+    }
+    if bots.is_empty() {
+        return 1;
+    }
+
+    loop {
+        bots.retain_mut(|bot| bot.play() != Turn::Over);
+        if bots.is_empty() {
+            println!("Game {} is over for the bots", game_id);
+            return 0;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,113 @@
+use log::{debug, info};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+// Returns the directory holding the user's configuration files:
+// $PEWCRAFT_CONFIG_DIR, or $XDG_CONFIG_HOME/pewcraft, or $HOME/.config/pewcraft
+pub fn dir() -> Option<PathBuf> {
+    xdg_dir("PEWCRAFT_CONFIG_DIR", "XDG_CONFIG_HOME", &[".config"])
+}
+
+// Returns the directory holding what the client stores by itself (sessions...):
+// $PEWCRAFT_DATA_DIR, or $XDG_DATA_HOME/pewcraft, or $HOME/.local/share/pewcraft
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("PEWCRAFT_DATA_DIR", "XDG_DATA_HOME", &[".local", "share"])
+}
+
+// Returns the directory holding what can be thrown away at any time (downloaded data...):
+// $PEWCRAFT_CACHE_DIR, or $XDG_CACHE_HOME/pewcraft, or $HOME/.cache/pewcraft
+pub fn cache_dir() -> Option<PathBuf> {
+    xdg_dir("PEWCRAFT_CACHE_DIR", "XDG_CACHE_HOME", &[".cache"])
+}
+
+fn xdg_dir(own_var: &str, xdg_var: &str, home_default: &[&str]) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(own_var) {
+        return Some(PathBuf::from(dir));
+    }
+
+    match env::var_os(xdg_var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("pewcraft")),
+        _ => env::var_os("HOME").map(|home| {
+            home_default
+                .iter()
+                .fold(PathBuf::from(home), |path, d| path.join(d))
+                .join("pewcraft")
+        }),
+    }
+}
+
+// Reads and decodes the given file from the configuration directory.
+// A missing file is not an error: Ok(None) is returned and the caller uses its defaults.
+pub fn read<T: DeserializeOwned>(name: &str) -> Result<Option<T>, String> {
+    read_in(dir(), name)
+}
+
+// Same as `read`, from the data directory
+pub fn read_data<T: DeserializeOwned>(name: &str) -> Result<Option<T>, String> {
+    read_in(data_dir(), name)
+}
+
+fn read_in<T: DeserializeOwned>(dir: Option<PathBuf>, name: &str) -> Result<Option<T>, String> {
+    let path = match dir {
+        Some(dir) => dir.join(name),
+        None => return Ok(None),
+    };
+
+    debug!("Reading {:?}", path);
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            info!("No file at {:?}, using defaults", path);
+            Ok(None)
+        }
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+// Same as `read`, from the cache directory
+pub fn read_cache<T: DeserializeOwned>(name: &str) -> Result<Option<T>, String> {
+    read_in(cache_dir(), name)
+}
+
+// Writes the value to the given file of the data directory, creating the directory if needed
+pub fn write_data<T: Serialize>(name: &str, value: &T) -> Result<(), String> {
+    write_in(data_dir(), name, value)
+}
+
+// Same as `write_data`, to the cache directory
+pub fn write_cache<T: Serialize>(name: &str, value: &T) -> Result<(), String> {
+    write_in(cache_dir(), name, value)
+}
+
+// Removes the given file of the data directory, if it exists
+pub fn remove_data(name: &str) -> Result<(), String> {
+    let path = match data_dir() {
+        Some(dir) => dir.join(name),
+        None => return Ok(()),
+    };
+
+    debug!("Removing {:?}", path);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(format!("{}: {}", path.display(), e)),
+        _ => Ok(()),
+    }
+}
+
+fn write_in<T: Serialize>(dir: Option<PathBuf>, name: &str, value: &T) -> Result<(), String> {
+    let path = dir
+        .ok_or_else(|| "no home directory (is $HOME set?)".to_owned())?
+        .join(name);
+    let dir = path.parent().unwrap();
+
+    debug!("Writing {:?}", path);
+    let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&path, content))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
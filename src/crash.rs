@@ -0,0 +1,82 @@
+use crate::tui;
+use pewcraft_frontend::config;
+use pewcraft_frontend::history;
+use std::backtrace::Backtrace;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Installs a panic hook that gives the terminal back to the user before anything else, then
+// writes everything we know about the crash to a file and tells the user where it is.
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        tui::restore_terminal();
+
+        let report = report(info);
+        match write(&report) {
+            Ok(path) => eprintln!(
+                "pewcraft crashed, sorry! A crash report was written to {}",
+                path.display()
+            ),
+            Err(e) => eprintln!(
+                "pewcraft crashed, sorry! The crash report could not be written ({}):\n{}",
+                e, report
+            ),
+        }
+
+        default_hook(info);
+    }));
+}
+
+fn report(info: &PanicHookInfo) -> String {
+    let mut report = String::new();
+    // writing to a String cannot fail
+    let _ = writeln!(report, "pewcraft_frontend {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "\n== Panic\n{}", info);
+    let _ = writeln!(report, "\n== Backtrace\n{}", Backtrace::force_capture());
+    let _ = writeln!(report, "\n== Current state\n{}", history::state());
+
+    let _ = writeln!(report, "\n== Recent events (oldest first)");
+    for event in history::events() {
+        let _ = writeln!(report, "{}", event);
+    }
+
+    let _ = writeln!(report, "\n== Recent HTTP exchanges (oldest first)");
+    for exchange in history::exchanges() {
+        let _ = writeln!(
+            report,
+            "{} {} -> {} in {:?}{}",
+            exchange.method,
+            exchange.path,
+            exchange
+                .status
+                .map_or_else(|| "no response".to_owned(), |s| s.to_string()),
+            exchange.latency,
+            exchange
+                .error
+                .map_or_else(String::new, |e| format!(" ({})", e)),
+        );
+    }
+
+    report
+}
+
+fn write(report: &str) -> Result<PathBuf, String> {
+    let dir = config::data_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join("crashes");
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("crash-{}.txt", timestamp));
+
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&path, report))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
@@ -0,0 +1,125 @@
+use crate::config;
+use log::{info, warn};
+use pewcraft_common::game::GameMap;
+use serde::{Deserialize, Serialize};
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+// The maps the user marked, listed first when choosing the map of a new game.
+// Their ids depend on the order of the game definition and several maps may have the same name:
+// maps are known by their name and a hash of their content, e.g. "Arena#8c6f0a3b5d2e4f17".
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Favorites {
+    maps: Vec<String>,
+}
+
+impl Favorites {
+    pub fn load() -> Self {
+        match config::read_data::<Favorites>(FAVORITES_FILE) {
+            Ok(favorites) => favorites.unwrap_or_default(),
+            Err(e) => {
+                warn!("Could not load the favorite maps: {}", e);
+                Favorites::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        match config::write_data(FAVORITES_FILE, self) {
+            Ok(()) => info!("Favorite maps stored: {:?}", self.maps),
+            Err(e) => warn!("Could not store the favorite maps: {}", e),
+        }
+    }
+
+    pub fn contains(&self, map: &GameMap) -> bool {
+        self.contains_key(&key(map))
+    }
+
+    // Marks the map, or unmarks it if it already was
+    pub fn toggle(&mut self, map: &GameMap) {
+        self.toggle_key(key(map));
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.maps.iter().any(|m| m == key)
+    }
+
+    fn toggle_key(&mut self, key: String) {
+        if self.contains_key(&key) {
+            self.maps.retain(|m| *m != key);
+        } else {
+            self.maps.push(key);
+        }
+    }
+}
+
+fn key(map: &GameMap) -> String {
+    // a map is plain data, serializing it cannot fail
+    let content = serde_json::to_vec(map).unwrap();
+    format!("{}#{:016x}", map.name, fnv1a(&content))
+}
+
+// 64 bits FNV-1a, as the keys are stored: the std hashers may change between releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn fnv1a_matches_the_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn toggling_marks_then_unmarks() {
+        let mut favorites = Favorites::default();
+        favorites.toggle_key("Arena#1".to_owned());
+        assert!(favorites.contains_key("Arena#1"));
+        favorites.toggle_key("Arena#1".to_owned());
+        assert!(!favorites.contains_key("Arena#1"));
+    }
+
+    fn arena(data: serde_json::Value, teams: serde_json::Value) -> GameMap {
+        serde_json::from_value(json!({
+            "name": "Arena",
+            "width": 2,
+            "height": 1,
+            "data": data,
+            "teams": teams
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn keys_are_stable() {
+        let map = || arena(json!(["Floor", "Floor"]), json!([["Red", [0]]]));
+        assert_eq!(key(&map()), key(&map()));
+        assert!(key(&map()).starts_with("Arena#"));
+    }
+
+    #[test]
+    fn maps_with_the_same_name_are_told_apart() {
+        let floors = json!(["Floor", "Floor"]);
+        let red = json!([["Red", [0]]]);
+        let a = arena(floors.clone(), red.clone());
+        let other_data = arena(json!(["Floor", "Wall"]), red);
+        let other_teams = arena(floors, json!([["Red", [1]]]));
+        assert_ne!(key(&a), key(&other_data));
+        assert_ne!(key(&a), key(&other_teams));
+
+        let mut favorites = Favorites::default();
+        favorites.toggle(&a);
+        assert!(favorites.contains(&a));
+        assert!(!favorites.contains(&other_data));
+        assert!(!favorites.contains(&other_teams));
+    }
+}
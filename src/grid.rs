@@ -0,0 +1,40 @@
+use pewcraft_common::game::{Cell, GameMap, Id};
+
+// Geometry of the square grid of a map
+
+// Number of steps between two cells, moving horizontally and vertically
+pub fn distance(map: &GameMap, a: Id<Cell>, b: Id<Cell>) -> usize {
+    let (ax, ay) = map.id_to_xy(a);
+    let (bx, by) = map.id_to_xy(b);
+    (ax as isize - bx as isize).abs() as usize + (ay as isize - by as isize).abs() as usize
+}
+
+// The cells one step away
+pub fn neighbours(map: &GameMap, cell: Id<Cell>) -> Vec<Id<Cell>> {
+    let (x, y) = map.id_to_xy(cell);
+    let mut neighbours = Vec::new();
+    if x > 0 {
+        neighbours.push(Id::new(cell.raw() - 1));
+    }
+    if x + 1 < map.width {
+        neighbours.push(Id::new(cell.raw() + 1));
+    }
+    if y > 0 {
+        neighbours.push(Id::new(cell.raw() - map.width));
+    }
+    if y + 1 < map.height {
+        neighbours.push(Id::new(cell.raw() + map.width));
+    }
+    neighbours
+}
+
+// The cell at the given offset, None past the edges of the map
+pub fn step(map: &GameMap, cell: Id<Cell>, dx: isize, dy: isize) -> Option<Id<Cell>> {
+    let (x, y) = map.id_to_xy(cell);
+    let (x, y) = (x as isize + dx, y as isize + dy);
+    if x < 0 || y < 0 || x >= map.width as isize || y >= map.height as isize {
+        None
+    } else {
+        Some(Id::new(y as usize * map.width + x as usize))
+    }
+}
@@ -0,0 +1,71 @@
+use crate::state::GlobalState;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+// What happened recently, kept around for crash reports and live diagnostics
+
+const MAX_EVENTS: usize = 50;
+const MAX_EXCHANGES: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub method: &'static str,
+    pub path: String,
+    // None when no response was received at all
+    pub status: Option<u16>,
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+static EVENTS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static EXCHANGES: Mutex<VecDeque<Exchange>> = Mutex::new(VecDeque::new());
+static STATE: Mutex<String> = Mutex::new(String::new());
+
+// The history is also read from the panic hook: a poisoned lock must not prevent that
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn push<T>(queue: &mut VecDeque<T>, value: T, max: usize) {
+    if queue.len() == max {
+        queue.pop_front();
+    }
+    queue.push_back(value);
+}
+
+pub fn record_event<E: Debug>(event: &E) {
+    push(&mut lock(&EVENTS), format!("{:?}", event), MAX_EVENTS);
+}
+
+pub fn record_exchange(exchange: Exchange) {
+    push(&mut lock(&EXCHANGES), exchange, MAX_EXCHANGES);
+}
+
+// Only a summary: the whole state includes the game definition, and is recorded on every frame
+pub fn record_state(state: &GlobalState) {
+    let game_id = if state.exit() {
+        None
+    } else {
+        state.get_game_id()
+    };
+    *lock(&STATE) = match game_id {
+        Some(game_id) => format!("{} in game {}", state.variant(), game_id),
+        None => state.variant().to_owned(),
+    };
+}
+
+// Oldest first
+pub fn events() -> Vec<String> {
+    lock(&EVENTS).iter().cloned().collect()
+}
+
+// Oldest first
+pub fn exchanges() -> Vec<Exchange> {
+    lock(&EXCHANGES).iter().cloned().collect()
+}
+
+pub fn state() -> String {
+    lock(&STATE).clone()
+}
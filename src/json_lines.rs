@@ -0,0 +1,231 @@
+use log::{debug, info};
+use pewcraft_common::game::{Cell, Character, Class, GameDefinition, GameMap, GameState, Id, Team};
+use pewcraft_common::io::{WireCreatedChar, WireNewCharRequest, WireNewGameRequest};
+use pewcraft_frontend::api::{Endpoint, WireAction, WireProgress};
+use pewcraft_frontend::rules;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+// A front end for programs rather than humans: one JSON command per line on stdin, one JSON
+// message per line on stdout. Bots written in any language can play through our Endpoint, e.g.:
+//   {"command": "create_game", "map": 0, "team_size": 1}
+//   {"command": "join", "game_id": "...", "name": "Bob", "class": 0, "team": 0, "position": 3}
+//   {"command": "state"}
+//   {"command": "act", "action": {"Move": 4}}
+// Every command is answered by exactly one message, with an "error" type when it failed.
+// Users learn the protocol from the usage text (cli.rs), which must follow its changes.
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Command {
+    Definition,
+    CreateGame {
+        map: Id<GameMap>,
+        team_size: usize,
+    },
+    Join {
+        game_id: String,
+        name: String,
+        class: Id<Class>,
+        team: Id<Team>,
+        position: Id<Cell>,
+    },
+    State,
+    Act {
+        action: WireAction,
+    },
+    Quit,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message<'a> {
+    Definition {
+        definition: &'a GameDefinition,
+    },
+    Game {
+        game_id: String,
+        map: Id<GameMap>,
+    },
+    State {
+        game_id: &'a str,
+        login: &'a str,
+        character: Id<Character>,
+        state: GameState,
+        // whose turn it is and who is out, None when the server could not tell
+        progress: Option<WireProgress>,
+        // what we could do if it is our turn
+        legal_actions: Vec<WireAction>,
+    },
+    Ok,
+    Error {
+        message: String,
+    },
+}
+
+// The character we play, once joined
+struct Player {
+    game_id: String,
+    login: String,
+    id: Id<Character>,
+}
+
+fn send<W: Write>(out: &mut W, message: &Message) -> io::Result<()> {
+    writeln!(out, "{}", serde_json::to_string(message).unwrap())?;
+    out.flush()
+}
+
+fn error(message: String) -> Message<'static> {
+    Message::Error { message }
+}
+
+pub fn run(endpoint: &Endpoint, game: &GameDefinition) -> i32 {
+    info!("Speaking JSON lines on stdin/stdout");
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut player: Option<Player> = None;
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Could not read stdin: {}", e);
+                return 1;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        debug!("Received command: {}", line);
+
+        let command = match serde_json::from_str::<Command>(&line) {
+            Ok(command) => command,
+            Err(e) => {
+                if send(&mut out, &error(format!("invalid command: {}", e))).is_err() {
+                    return 1;
+                }
+                continue;
+            }
+        };
+
+        if matches!(command, Command::Quit) {
+            return 0;
+        }
+        // a bug must not leave the command unanswered, the panic hook reports it on stderr
+        let handled = panic::catch_unwind(AssertUnwindSafe(|| match command {
+            Command::Quit => Ok(()),
+            Command::Definition => send(&mut out, &Message::Definition { definition: game }),
+            Command::CreateGame { map, team_size } => {
+                let message = if game.maps.get(map).is_none() {
+                    error(format!("no map {}", map.raw()))
+                } else {
+                    match endpoint.create_game(WireNewGameRequest { map, team_size }) {
+                        Ok(created) => Message::Game {
+                            game_id: created.game_id,
+                            map: created.map,
+                        },
+                        Err(e) => error(format!("could not create the game: {}", e)),
+                    }
+                };
+                send(&mut out, &message)
+            }
+            Command::Join {
+                game_id,
+                name,
+                class,
+                team,
+                position,
+            } => {
+                let request = WireNewCharRequest {
+                    name,
+                    class,
+                    team,
+                    position,
+                };
+                match endpoint.create_char(&game_id, request) {
+                    Ok(WireCreatedChar(login, id)) => {
+                        let joined = Player { game_id, login, id };
+                        let sent = send(&mut out, &state(endpoint, game, &joined));
+                        player = Some(joined);
+                        sent
+                    }
+                    Err(e) => send(&mut out, &error(e.to_string())),
+                }
+            }
+            Command::State => match &player {
+                Some(player) => send(&mut out, &state(endpoint, game, player)),
+                None => send(&mut out, &error("join a game first".to_owned())),
+            },
+            Command::Act { action } => match &player {
+                Some(player) => {
+                    let message =
+                        match endpoint.act(player.game_id.as_str(), player.login.as_str(), &action)
+                        {
+                            Ok(()) => Message::Ok,
+                            Err(e) => error(e.to_string()),
+                        };
+                    send(&mut out, &message)
+                }
+                None => send(&mut out, &error("join a game first".to_owned())),
+            },
+        }));
+        let sent = match handled {
+            Ok(sent) => sent,
+            Err(_) => send(
+                &mut out,
+                &error("internal error, see the crash report".to_owned()),
+            ),
+        };
+
+        // nobody is listening anymore
+        if sent.is_err() {
+            return 1;
+        }
+    }
+
+    0
+}
+
+fn state<'a>(endpoint: &Endpoint, game: &GameDefinition, player: &'a Player) -> Message<'a> {
+    let state = match endpoint.game_state(&player.game_id) {
+        Ok(Some(state)) => state,
+        Ok(None) => return error(format!("no game {}", player.game_id)),
+        Err(e) => {
+            return error(format!(
+                "could not get the state of game {}: {}",
+                player.game_id, e
+            ))
+        }
+    };
+    let progress = match endpoint.progress(&player.game_id) {
+        Ok(progress) => Some(progress),
+        Err(e) => {
+            debug!(
+                "Could not get the progress of game {}: {}",
+                player.game_id, e
+            );
+            None
+        }
+    };
+    // the rules only tell what the character could do, whoever's turn it is
+    let our_turn = progress
+        .as_ref()
+        .map_or(false, |p| p.turn == Some(player.id) && p.winner.is_none());
+    let legal_actions = match (game.maps.get(state.map), &progress) {
+        (Some(map), Some(progress)) if our_turn => {
+            rules::legal_actions(map, &state, &progress.defeated, player.id)
+        }
+        _ => Vec::new(),
+    };
+
+    Message::State {
+        game_id: &player.game_id,
+        login: &player.login,
+        character: player.id,
+        state,
+        progress,
+        legal_actions,
+    }
+}
@@ -0,0 +1,26 @@
+// The client library: the API client, the state machine of the client and the models they use,
+// independently of how they are shown. The terminal client (main.rs) is one of its consumers,
+// bots and tools can drive the same `state::GlobalState` with an `api::Endpoint` of their own.
+//
+// The API is `api`, `bot`, `grid`, `rules`, `state` and `validate`. The hidden modules are shared
+// with the terminal client only, and may change with it.
+
+pub mod api;
+pub mod bot;
+pub mod grid;
+pub mod rules;
+pub mod state;
+pub mod validate;
+
+pub use api::Endpoint;
+
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod history;
+#[doc(hidden)]
+pub mod session;
+
+mod cassette;
+mod favorites;
+mod local;
@@ -0,0 +1,400 @@
+use crate::api::{WireAction, WireProgress};
+use crate::grid::distance;
+use crate::rules::walkable;
+use log::{debug, info};
+use pewcraft_common::game::{Cell, Character, GameDefinition, GameState, Id, Team};
+use pewcraft_common::io::{
+    WireCreatedChar, WireCreatedGame, WireNewCharRequest, WireNewGameRequest,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// An in-process replacement for the pewcraft server, so that the client can be played (and
+// tested) with no network at all. It answers the requests the client sends, the way the client
+// expects them to be answered: that includes the requests pewcraft_common does not define yet
+// (see WireAction and WireProgress in api.rs), so it is no reference for the server's behaviour.
+
+// Ids of games and logins are as long as the server's
+const ID_LENGTH: usize = 10;
+const ID_CHARACTERS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+// The game definition has no stats yet: every character is defeated after that many attacks
+const HEALTH: u32 = 3;
+
+// A character, as the local server keeps track of it
+#[derive(Debug)]
+struct Player {
+    login: String,
+    id: Id<Character>,
+    team: Id<Team>,
+    position: Id<Cell>,
+    // attacks left before the character is defeated
+    health: u32,
+    // false once the player left, the character can be resumed with its login
+    connected: bool,
+}
+
+impl Player {
+    fn is_defeated(&self) -> bool {
+        self.health == 0
+    }
+}
+
+#[derive(Debug)]
+struct LocalGame {
+    state: GameState,
+    team_size: usize,
+    players: Vec<Player>,
+    // index in `players` of the character whose turn it is, once the game started
+    turn: Option<usize>,
+    // a character can either move or attack once per turn
+    acted: bool,
+    winner: Option<Id<Team>>,
+}
+
+impl LocalGame {
+    fn is_full(&self, teams: usize) -> bool {
+        self.players.len() == teams * self.team_size
+    }
+
+    // The game starts as soon as every slot is taken, each character then plays in turn.
+    // Defeated characters are skipped, so are the ones which left unless nobody is left.
+    fn advance(&mut self) {
+        let count = self.players.len();
+        let start = self.turn.map_or(0, |turn| turn + 1);
+        let standing: Vec<usize> = (0..count)
+            .map(|offset| (start + offset) % count)
+            .filter(|&index| !self.players[index].is_defeated())
+            .collect();
+        self.turn = standing
+            .iter()
+            .find(|&&index| self.players[index].connected)
+            .or_else(|| standing.first())
+            .copied();
+        self.acted = false;
+        if let Some(turn) = self.turn {
+            debug!("Turn of {}", self.players[turn].login);
+        }
+    }
+
+    // The game is over once a single team has characters standing
+    fn update_winner(&mut self) {
+        let mut teams = self
+            .players
+            .iter()
+            .filter(|p| !p.is_defeated())
+            .map(|p| p.team);
+        if let Some(team) = teams.next() {
+            if teams.all(|other| other == team) {
+                info!("Team {} won", team.raw());
+                self.winner = Some(team);
+            }
+        }
+    }
+
+    fn progress(&self) -> WireProgress {
+        WireProgress {
+            turn: self.turn.map(|turn| self.players[turn].id),
+            defeated: self
+                .players
+                .iter()
+                .filter(|p| p.is_defeated())
+                .map(|p| p.id)
+                .collect(),
+            winner: self.winner,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalServer {
+    definition: GameDefinition,
+    games: Mutex<HashMap<String, LocalGame>>,
+    next_id: Mutex<u64>,
+}
+
+// An answer of the local server: the HTTP status the real server would use, and the JSON body
+type Answer = (u16, Value);
+
+fn error(status: u16, message: String) -> Answer {
+    debug!("Local server error {}: {}", status, message);
+    (status, json!({ "error": message }))
+}
+
+impl LocalServer {
+    pub fn new(definition: GameDefinition) -> Self {
+        info!("Starting a local server");
+        LocalServer {
+            definition,
+            games: Mutex::new(HashMap::new()),
+            next_id: Mutex::new(0),
+        }
+    }
+
+    // Random-looking ids, like the server's: the time avoids getting the same ids as a previous
+    // run, which could make an old session resume the wrong game
+    fn new_id(&self) -> String {
+        let mut next_id = self.next_id.lock().unwrap();
+        *next_id += 1;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        // a xorshift generator, seeded with both
+        let mut state = (nanos ^ next_id.wrapping_mul(0x9e37_79b9_7f4a_7c15)) | 1;
+        (0..ID_LENGTH)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                ID_CHARACTERS[(state % ID_CHARACTERS.len() as u64) as usize] as char
+            })
+            .collect()
+    }
+
+    pub fn handle(&self, method: &str, path: &str, body: &Option<Value>) -> Answer {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        match (method, segments.as_slice(), body) {
+            ("GET", ["version"], _) => (
+                200,
+                json!({ "protocol": crate::api::SUPPORTED_PROTOCOLS.end(), "server": "local" }),
+            ),
+            ("GET", ["game"], _) => (200, serde_json::to_value(&self.definition).unwrap()),
+            ("POST", ["new_game"], Some(body)) => match serde_json::from_value(body.clone()) {
+                Ok(request) => self.new_game(request),
+                Err(e) => error(400, e.to_string()),
+            },
+            ("GET", [game_id], _) => self.game_state(game_id),
+            ("GET", [game_id, "progress"], _) => self.progress(game_id),
+            ("POST", [game_id], Some(body)) => match serde_json::from_value(body.clone()) {
+                Ok(request) => self.new_char(game_id, request),
+                Err(e) => error(400, e.to_string()),
+            },
+            ("POST", [game_id, login], None) => self.rejoin(game_id, login),
+            ("POST", [game_id, login, "leave"], None) => self.leave(game_id, login),
+            ("POST", [game_id, login, "action"], Some(body)) => {
+                match serde_json::from_value(body.clone()) {
+                    Ok(action) => self.action(game_id, login, action),
+                    Err(e) => error(400, e.to_string()),
+                }
+            }
+            _ => error(404, format!("{} {}", method, path)),
+        }
+    }
+
+    fn new_game(&self, request: WireNewGameRequest) -> Answer {
+        if self.definition.maps.get(request.map).is_none() {
+            return error(404, format!("no map {}", request.map.raw()));
+        }
+        if request.team_size == 0 {
+            return error(400, "teams cannot be empty".to_owned());
+        }
+
+        let game_id = self.new_id();
+        info!("Local game {} created: {:?}", game_id, request);
+        self.games.lock().unwrap().insert(
+            game_id.clone(),
+            LocalGame {
+                state: GameState::new(request.map),
+                team_size: request.team_size,
+                players: Vec::new(),
+                turn: None,
+                acted: false,
+                winner: None,
+            },
+        );
+        let created = WireCreatedGame {
+            game_id,
+            map: request.map,
+        };
+        (200, serde_json::to_value(&created).unwrap())
+    }
+
+    fn game_state(&self, game_id: &str) -> Answer {
+        match self.games.lock().unwrap().get(game_id) {
+            Some(game) => (200, serde_json::to_value(&game.state).unwrap()),
+            None => error(404, format!("no game {}", game_id)),
+        }
+    }
+
+    fn progress(&self, game_id: &str) -> Answer {
+        match self.games.lock().unwrap().get(game_id) {
+            Some(game) => (200, serde_json::to_value(&game.progress()).unwrap()),
+            None => error(404, format!("no game {}", game_id)),
+        }
+    }
+
+    fn new_char(&self, game_id: &str, request: WireNewCharRequest) -> Answer {
+        let mut games = self.games.lock().unwrap();
+        let game = match games.get_mut(game_id) {
+            Some(game) => game,
+            None => return error(404, format!("no game {}", game_id)),
+        };
+        let map = self.definition.maps.get(game.state.map).unwrap();
+
+        if self.definition.classes.get(request.class).is_none() {
+            return error(400, format!("no class {}", request.class.raw()));
+        }
+        let spawns = match map.teams.get(request.team.raw()) {
+            Some((_, spawns)) => spawns,
+            None => return error(400, format!("no team {}", request.team.raw())),
+        };
+        if game.is_full(map.teams.len()) {
+            return error(409, "the game is full".to_owned());
+        }
+        if game
+            .players
+            .iter()
+            .filter(|p| p.team == request.team)
+            .count()
+            == game.team_size
+        {
+            return error(409, format!("team {} is full", request.team.raw()));
+        }
+        if !spawns.contains(&request.position) {
+            return error(
+                400,
+                format!("cell {} is not a spawn of the team", request.position.raw()),
+            );
+        }
+        if game.players.iter().any(|p| p.position == request.position) {
+            return error(
+                409,
+                format!("cell {} is already taken", request.position.raw()),
+            );
+        }
+
+        let login = self.new_id();
+        let team = request.team;
+        let position = request.position;
+        let id = game.state.add_char(Character::new(
+            request.name,
+            request.class,
+            request.team,
+            request.position,
+        ));
+        debug!(
+            "Character {} ({}) joined local game {}",
+            id.raw(),
+            login,
+            game_id
+        );
+        game.players.push(Player {
+            login: login.clone(),
+            id,
+            team,
+            position,
+            health: HEALTH,
+            connected: true,
+        });
+        if game.is_full(map.teams.len()) {
+            info!("Local game {} is full, starting it", game_id);
+            game.advance();
+        }
+
+        (
+            200,
+            serde_json::to_value(&WireCreatedChar(login, id)).unwrap(),
+        )
+    }
+
+    // Resumes a character which left the game
+    fn rejoin(&self, game_id: &str, login: &str) -> Answer {
+        let mut games = self.games.lock().unwrap();
+        let game = match games.get_mut(game_id) {
+            Some(game) => game,
+            None => return error(404, format!("no game {}", game_id)),
+        };
+        match game.players.iter_mut().find(|p| p.login == login) {
+            Some(player) => {
+                player.connected = true;
+                let rejoined = WireCreatedChar(player.login.clone(), player.id);
+                (200, serde_json::to_value(&rejoined).unwrap())
+            }
+            None => error(404, format!("no character {} in game {}", login, game_id)),
+        }
+    }
+
+    fn leave(&self, game_id: &str, login: &str) -> Answer {
+        let mut games = self.games.lock().unwrap();
+        let player = games
+            .get_mut(game_id)
+            .and_then(|game| game.players.iter_mut().find(|p| p.login == login));
+        match player {
+            Some(player) => {
+                player.connected = false;
+                (200, Value::Null)
+            }
+            None => error(404, format!("no character {} in game {}", login, game_id)),
+        }
+    }
+
+    fn action(&self, game_id: &str, login: &str, action: WireAction) -> Answer {
+        let mut games = self.games.lock().unwrap();
+        let game = match games.get_mut(game_id) {
+            Some(game) => game,
+            None => return error(404, format!("no game {}", game_id)),
+        };
+        // ending the turn of the winning attack is fine, there is just no next turn
+        match (game.winner, &action) {
+            (Some(_), WireAction::EndTurn) => return (200, Value::Null),
+            (Some(_), _) => return error(409, "the game is over".to_owned()),
+            (None, _) => (),
+        }
+        let turn = match game.turn {
+            Some(turn) if game.players[turn].login == login => turn,
+            Some(_) => return error(409, "this is not your turn".to_owned()),
+            None => return error(409, "the game has not started".to_owned()),
+        };
+        let map = self.definition.maps.get(game.state.map).unwrap();
+        let position = game.players[turn].position;
+
+        match action {
+            WireAction::Move(_) | WireAction::Attack(_) if game.acted => {
+                error(409, "you already acted this turn".to_owned())
+            }
+            WireAction::Move(cell) => {
+                if cell.raw() >= map.data.len() || distance(map, position, cell) != 1 {
+                    return error(400, format!("cell {} is not a neighbour", cell.raw()));
+                }
+                if !walkable(map, cell) {
+                    return error(400, format!("cell {} cannot be walked on", cell.raw()));
+                }
+                if game.players.iter().any(|p| p.position == cell) {
+                    return error(409, format!("cell {} is already taken", cell.raw()));
+                }
+                let player = &mut game.players[turn];
+                player.position = cell;
+                game.state.characters.get_mut(player.id).unwrap().position = cell;
+                game.acted = true;
+                (200, Value::Null)
+            }
+            WireAction::Attack(target) => {
+                let team = game.players[turn].team;
+                match game.players.iter_mut().find(|p| p.id == target) {
+                    Some(p) if p.team == team => error(400, "this is an ally".to_owned()),
+                    Some(p) if p.is_defeated() => {
+                        error(409, "the target is already defeated".to_owned())
+                    }
+                    Some(p) if distance(map, position, p.position) != 1 => {
+                        error(400, "the target is out of reach".to_owned())
+                    }
+                    Some(p) => {
+                        p.health -= 1;
+                        if p.is_defeated() {
+                            info!("{} was defeated in local game {}", p.login, game_id);
+                        }
+                        game.acted = true;
+                        game.update_winner();
+                        (200, Value::Null)
+                    }
+                    None => error(404, format!("no character {}", target.raw())),
+                }
+            }
+            WireAction::EndTurn => {
+                game.advance();
+                (200, Value::Null)
+            }
+        }
+    }
+}
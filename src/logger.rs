@@ -0,0 +1,205 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use pewcraft_frontend::config;
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Logs go to a rotating file (stderr would be drawn over by the TUI) and to an in-memory buffer
+// shown by the in-app log console.
+//
+// The verbosity is set with $PEWCRAFT_LOG (or $RUST_LOG), as a default level optionally followed
+// by per module levels, e.g. "info,pewcraft_frontend::api=debug".
+
+const LOG_FILE: &str = "pewcraft.log";
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+// pewcraft.log.1 ... pewcraft.log.3
+const ROTATED_FILES: usize = 3;
+const MAX_RECORDS: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+struct Logger {
+    // (module prefix, level), the longest matching prefix wins
+    directives: Mutex<Vec<(Option<String>, LevelFilter)>>,
+    file: Mutex<Option<LogFile>>,
+    records: Mutex<VecDeque<LogRecord>>,
+}
+
+static LOGGER: Logger = Logger {
+    directives: Mutex::new(Vec::new()),
+    file: Mutex::new(None),
+    records: Mutex::new(VecDeque::new()),
+};
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Returns the path of the log file, if it could be opened
+pub fn init() -> Option<PathBuf> {
+    let spec = env::var("PEWCRAFT_LOG")
+        .or_else(|_| env::var("RUST_LOG"))
+        .unwrap_or_default();
+    let directives = parse_directives(&spec);
+    let max_level = directives
+        .iter()
+        .map(|(_, level)| *level)
+        .max()
+        .unwrap_or(LevelFilter::Info);
+    *lock(&LOGGER.directives) = directives;
+
+    let path = config::data_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join("logs")
+        .join(LOG_FILE);
+    let file = open(&path);
+    let opened = file.as_ref().map(|f| f.path.clone());
+    *lock(&LOGGER.file) = file;
+
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(max_level);
+    opened
+}
+
+fn parse_directives(spec: &str) -> Vec<(Option<String>, LevelFilter)> {
+    let mut directives: Vec<(Option<String>, LevelFilter)> = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .filter_map(|d| {
+            let mut parts = d.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(module), Some(level)) => level
+                    .parse()
+                    .ok()
+                    .map(|level| (Some(module.to_owned()), level)),
+                (Some(level_or_module), None) => match level_or_module.parse() {
+                    Ok(level) => Some((None, level)),
+                    // a lone module name means "everything from that module"
+                    Err(_) => Some((Some(level_or_module.to_owned()), LevelFilter::Trace)),
+                },
+                _ => None,
+            }
+        })
+        .collect();
+
+    if !directives.iter().any(|(module, _)| module.is_none()) {
+        directives.push((None, LevelFilter::Info));
+    }
+    directives
+}
+
+fn open(path: &PathBuf) -> Option<LogFile> {
+    fs::create_dir_all(path.parent()?).ok()?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .ok()?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Some(LogFile {
+        path: path.clone(),
+        file,
+        size,
+    })
+}
+
+impl LogFile {
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) {
+        for index in (1..ROTATED_FILES).rev() {
+            let _ = fs::rename(self.rotated(index), self.rotated(index + 1));
+        }
+        let _ = fs::rename(&self.path, self.rotated(1));
+        if let Some(file) = open(&self.path) {
+            *self = file;
+        }
+    }
+
+    fn write(&mut self, line: &str) {
+        if self.size + line.len() as u64 > MAX_FILE_SIZE {
+            self.rotate();
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let target = metadata.target();
+        let level = lock(&self.directives)
+            .iter()
+            .filter(|(module, _)| match module {
+                Some(module) => target.starts_with(module.as_str()),
+                None => true,
+            })
+            .max_by_key(|(module, _)| module.as_ref().map_or(0, |m| m.len()))
+            .map(|(_, level)| *level)
+            .unwrap_or(LevelFilter::Info);
+        metadata.level() <= level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = record.args().to_string();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        if let Some(file) = lock(&self.file).as_mut() {
+            file.write(&format!(
+                "{} {:<5} {}: {}\n",
+                timestamp,
+                record.level(),
+                record.target(),
+                message
+            ));
+        }
+
+        let mut records = lock(&self.records);
+        if records.len() == MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(LogRecord {
+            level: record.level(),
+            target: record.target().to_owned(),
+            message,
+        });
+    }
+
+    fn flush(&self) {
+        if let Some(file) = lock(&self.file).as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+// Oldest first
+pub fn records() -> Vec<LogRecord> {
+    lock(&LOGGER.records).iter().cloned().collect()
+}
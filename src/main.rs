@@ -1,33 +1,221 @@
-use crate::state::{GlobalState, State};
-use log::{debug, info};
+use log::{debug, info, warn};
+use pewcraft_common::game::GameDefinition;
+use pewcraft_frontend::session::Session;
+use pewcraft_frontend::state::{self, GlobalState, State};
+use pewcraft_frontend::{api, bot, history, validate};
+use std::fs;
 use std::io::{stdin, stdout};
+use std::process;
+use std::thread;
 
-mod api;
-mod state;
+mod cli;
+mod crash;
+mod json_lines;
+mod logger;
+mod plain;
 mod tui;
 
+// Errors which prevent the client from starting are shown full screen when playing that way,
+// and simply printed otherwise (e.g. a command, whose output may not even be a terminal)
+fn fatal(full_screen: bool, title: &str, message: &str) -> ! {
+    if full_screen {
+        tui::fatal_screen(title, message);
+    } else {
+        eprintln!("{}: {}", title, message);
+    }
+    process::exit(1);
+}
+
+// The full screen interface, or the line-based one
+enum Ui<'a> {
+    Tui(tui::Tui<'a>),
+    Plain(plain::Plain<'a>),
+}
+
+// Stops the bots of the pool when dropped
+struct StopBots<'p, 'a>(&'p bot::Pool<'a>);
+impl<'p, 'a> Drop for StopBots<'p, 'a> {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
+
+fn load_definition(path: &str) -> Result<GameDefinition, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e))
+}
+
 fn main() {
-    env_logger::init();
+    let log_file = logger::init();
+    crash::install();
+    match log_file {
+        Some(path) => info!("Logging to {}", path.display()),
+        None => eprintln!("Could not open the log file, logs will only be shown in the client"),
+    }
+
+    let options = cli::parse();
+    let interactive = matches!(options.command, cli::Command::Play);
+    // fatal errors can only be shown full screen when the game would be
+    let full_screen = interactive && !options.plain;
+    let url = &options.server;
+
+    let endpoint = match (&options.replay, &options.local) {
+        (Some(cassette), _) => match api::Endpoint::replay(url, cassette) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                eprintln!("Could not load the cassette: {}", e);
+                process::exit(1);
+            }
+        },
+        (None, Some(definition)) => match load_definition(definition) {
+            Ok(definition) => api::Endpoint::local(definition),
+            Err(e) => {
+                eprintln!("Could not load the game definition: {}", e);
+                process::exit(1);
+            }
+        },
+        (None, None) => {
+            let (settings, problem) = api::Settings::load();
+            if let Some(problem) = problem {
+                warn!("Network settings: {}", problem);
+                eprintln!("Network settings: {}", problem);
+            }
+            api::Endpoint::with_settings(url, settings)
+        }
+    };
+    let endpoint = match &options.record {
+        Some(cassette) => endpoint.record(cassette),
+        None => endpoint,
+    };
+    match endpoint.handshake() {
+        Ok(api::Handshake::Compatible(protocol)) => info!("Speaking protocol {}", protocol),
+        Ok(api::Handshake::ServerTooOld(protocol)) => {
+            fatal(
+                full_screen,
+                "Incompatible server",
+                &format!(
+                    "The server at {} speaks protocol {}, which is too old for this client \
+                     (supported: {} to {}).\nAsk its administrator to upgrade it.",
+                    url,
+                    protocol,
+                    api::SUPPORTED_PROTOCOLS.start(),
+                    api::SUPPORTED_PROTOCOLS.end()
+                ),
+            );
+        }
+        Ok(api::Handshake::ClientTooOld(protocol)) => {
+            fatal(
+                full_screen,
+                "Please upgrade",
+                &format!(
+                    "The server at {} speaks protocol {}, which is too recent for this client \
+                     (supported: {} to {}).\nPlease upgrade pewcraft_frontend.",
+                    url,
+                    protocol,
+                    api::SUPPORTED_PROTOCOLS.start(),
+                    api::SUPPORTED_PROTOCOLS.end()
+                ),
+            );
+        }
+        // the server answered, but not as a server we can play with
+        Err(e @ api::Error::Status(..)) | Err(e @ api::Error::Decode(_)) => {
+            fatal(
+                full_screen,
+                "Incompatible server",
+                &format!(
+                    "The server at {} did not tell which protocol it speaks: {}.\n\
+                     Check that it is a pewcraft server.",
+                    url, e
+                ),
+            );
+        }
+        // unreachable: we may still be able to start with a cached game definition
+        Err(e) => warn!("Version handshake failed: {}", e),
+    }
+
+    let game = match endpoint.load_game() {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Could not load the game from {}: {}", url, e);
+            process::exit(1);
+        }
+    };
+
+    if !interactive {
+        process::exit(cli::run(&options.command, &endpoint, &game, options.json));
+    }
 
-    let url = "http://localhost:8000";
-    let endpoint = api::Endpoint::new(url);
-    let game = endpoint.load_game();
+    let problems = validate::validate(&game);
+    if !problems.is_empty() {
+        let list: Vec<String> = problems.iter().map(|p| format!("- {}", p)).collect();
+        fatal(
+            full_screen,
+            "Invalid game definition",
+            &format!(
+                "The server sent a game definition the client cannot use:\n{}",
+                list.join("\n")
+            ),
+        );
+    }
+
+    // report problems before entering the alternate screen, they would not be visible otherwise
+    let (keymap, problems) = tui::Keymap::load();
+    for problem in problems {
+        warn!("Keymap: {}", problem);
+        eprintln!("Keymap: {}", problem);
+    }
+    let (theme, problems) = tui::Theme::load(options.glyphs);
+    for problem in problems {
+        warn!("Theme: {}", problem);
+        eprintln!("Theme: {}", problem);
+    }
 
     let stdin = stdin();
     let mut stdout = stdout();
-    let mut tui = tui::Tui::new(&game, &stdin, &mut stdout);
-    let mut s = GlobalState::new(&game, &endpoint);
+    let mut ui = if options.plain {
+        Ui::Plain(plain::Plain::new(&game))
+    } else {
+        Ui::Tui(tui::Tui::new(
+            &game,
+            &endpoint,
+            keymap,
+            theme,
+            &stdin,
+            &mut stdout,
+        ))
+    };
+    let bots = bot::Pool::new();
+    let mut s = match Session::load(endpoint.url()) {
+        Some(session) => GlobalState::resume(&game, &endpoint, &bots, session),
+        None => GlobalState::new(&game, &endpoint, &bots),
+    };
+
+    thread::scope(|scope| {
+        scope.spawn(|| bots.run());
+        // also when the game panics: the scope waits for the bots before unwinding any further
+        let _stop_bots = StopBots(&bots);
 
-    loop {
-        debug!("Current state: {:?}", s);
-        let input = tui.render(&s);
-        debug!("Received input: {:?}", input);
+        'game: loop {
+            debug!("Current state: {:?}", s);
+            history::record_state(&s);
+            let inputs = match &mut ui {
+                Ui::Tui(tui) => vec![tui.render(&s)],
+                Ui::Plain(plain) => plain.render(&s),
+            };
+            for input in inputs {
+                debug!("Received input: {:?}", input);
+                // timeouts would quickly push everything else out of the history
+                if !matches!(input, state::Event::Timeout) {
+                    history::record_event(&input);
+                }
 
-        s = s.next(input);
-        if s.exit() {
-            break;
+                s = s.next(input);
+                if s.exit() {
+                    break 'game;
+                }
+            }
         }
-    }
+    });
 
-    info!("Exiting tui");
+    info!("Exiting");
 }
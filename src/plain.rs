@@ -0,0 +1,454 @@
+use log::debug;
+use pewcraft_common::game::{Cell, Character, GameDefinition, GameMap, Id, IdMap};
+use pewcraft_frontend::api::WireAction;
+use pewcraft_frontend::grid::distance;
+use pewcraft_frontend::rules;
+use pewcraft_frontend::state::{
+    CreateCharacterState, CreateOrJoinState, Event, GlobalState, PlayGameState,
+    PlayGameStateDataImpl,
+};
+use std::io::{self, BufRead, Write};
+
+// A line-based front end, for terminals which cannot show the full screen one (or that do it
+// poorly, like screen readers). It prints what the full screen one draws as plain sentences and
+// numbered menus, and reads one line at a time.
+// Commands start with '/', so that anything else can be typed where text is expected (e.g. a
+// character named "q", or a game id made of digits). In menus, a bare number chooses an option.
+// An empty line refreshes the screen, e.g. to see whether the other players played.
+
+pub struct Plain<'a> {
+    game_definition: &'a GameDefinition,
+    // the last description printed, not repeated while nothing changed
+    last: String,
+}
+
+const COMMON_HELP: &str = "Type the number of an option, or a text where one is expected. \
+                           Commands: /N chooses option N, /q quits, /? shows this help, \
+                           /bot adds a bot, /sort changes the order of the maps. Start a text \
+                           with '//' to type it with a single '/'. Type nothing to refresh.";
+
+impl<'a> Plain<'a> {
+    pub fn new(game_definition: &'a GameDefinition) -> Self {
+        Plain {
+            game_definition,
+            last: String::new(),
+        }
+    }
+
+    // Describes the state, then turns the line typed by the user into events
+    pub fn render(&mut self, s: &GlobalState) -> Vec<Event> {
+        let (description, options) = self.describe(s);
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        if description != self.last {
+            let _ = writeln!(out, "\n{}", description);
+            // numbers are text where text is expected, options are then chosen with a command
+            let prefix = if expects_text(s) { "/" } else { "" };
+            for (index, option) in options.iter().enumerate() {
+                let _ = writeln!(out, "  {}{}) {}", prefix, index + 1, option);
+            }
+            self.last = description;
+        }
+        let _ = write!(out, "> ");
+        let _ = out.flush();
+
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            // end of the input, e.g. ctrl-d
+            Ok(0) | Err(_) => return vec![Event::Quit],
+            Ok(_) => {}
+        }
+        let line = line.trim();
+        debug!("Received line: {}", line);
+
+        if line.is_empty() {
+            // describe again, even if nothing changed
+            self.last.clear();
+            return vec![Event::Timeout];
+        }
+        let events = match parse(line, expects_text(s)) {
+            Line::Quit => Ok(vec![Event::Exit]),
+            Line::Help => Err(COMMON_HELP.to_owned()),
+            Line::AddBot => Ok(vec![Event::AddBot]),
+            Line::Sort => Ok(vec![Event::Sort]),
+            Line::Choose(n) if n >= 1 && n <= options.len() => Ok(choose(s, n - 1)),
+            Line::Choose(n) => Err(format!("There is no option {}.", n)),
+            Line::Text(typed) => {
+                text(s, typed).ok_or_else(|| format!("No text is expected here. {}", COMMON_HELP))
+            }
+            Line::Unknown(command) => {
+                Err(format!("Unknown command '{}'. {}", command, COMMON_HELP))
+            }
+        };
+        events.unwrap_or_else(|message| {
+            let _ = writeln!(out, "{}", message);
+            vec![Event::Other]
+        })
+    }
+
+    fn describe(&self, s: &GlobalState) -> (String, Vec<String>) {
+        let g = self.game_definition;
+        match s {
+            GlobalState::CreateOrJoin(CreateOrJoinState::Create(_)) => (
+                "Welcome to pewcraft!".to_owned(),
+                vec!["Create a new game".to_owned(), "Join a game".to_owned()],
+            ),
+            GlobalState::CreateOrJoin(CreateOrJoinState::Join(j)) => (
+                format!(
+                    "Type the login of the game to join (game id, or game id/character login). \
+                     Current login: '{}'.",
+                    j.curr().login
+                ),
+                vec![
+                    "Create a new game instead".to_owned(),
+                    "Join with the current login".to_owned(),
+                ],
+            ),
+            GlobalState::SelectMap(m) => {
+                let m = m.curr();
+                let favorites = &m.favorites;
+                let mut description = format!(
+                    "Choose the map of the new game. Type a part of a name to filter the maps \
+                     (current filter: '{}'), or /sort to change their order (current order: {}).",
+                    m.filter,
+                    m.sort.name()
+                );
+                if m.map_ids.is_empty() {
+                    description.push_str(" No map matches the filter.");
+                }
+                (
+                    description,
+                    m.map_ids
+                        .iter()
+                        .filter_map(|id| g.maps.get(*id))
+                        .map(|map| {
+                            let favorite = if favorites.contains(map) {
+                                " (favorite)"
+                            } else {
+                                ""
+                            };
+                            format!(
+                                "{}{}, {} by {} cells, {} teams",
+                                map.name,
+                                favorite,
+                                map.width,
+                                map.height,
+                                map.teams.len()
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            GlobalState::CreateCharacter(CreateCharacterState::Team(c)) => (
+                format!(
+                    "Game {}: choose your team (give this id to the other players).",
+                    c.curr().game_id
+                ),
+                c.curr()
+                    .map
+                    .teams
+                    .iter()
+                    .map(|(name, spawns)| format!("{}, {} spawn positions", name, spawns.len()))
+                    .collect(),
+            ),
+            GlobalState::CreateCharacter(CreateCharacterState::Class(c)) => (
+                "Choose your class.".to_owned(),
+                c.curr()
+                    .classes
+                    .iter()
+                    .filter_map(|id| g.classes.get(*id))
+                    .map(|class| format!("{}: {}", class.name, class.description))
+                    .collect(),
+            ),
+            GlobalState::CreateCharacter(CreateCharacterState::Position(c)) => {
+                let map = c.curr().map;
+                let spawns = &map.teams[c.curr().team_index].1;
+                (
+                    "Choose your starting position.".to_owned(),
+                    spawns.iter().map(|cell| coordinates(map, *cell)).collect(),
+                )
+            }
+            GlobalState::CreateCharacter(CreateCharacterState::Name(_)) => {
+                ("Type the name of your character.".to_owned(), vec![])
+            }
+            GlobalState::WaitForGameCreation(w) => (
+                format!(
+                    "Waiting for the other players. Your character login is {}/{}. \
+                     Type /bot to fill a slot with a bot.",
+                    w.curr().game_id,
+                    w.curr().login
+                ),
+                vec![],
+            ),
+            GlobalState::PlayGame(PlayGameState::OurTurn(p))
+            | GlobalState::PlayGame(PlayGameState::NotOurTurn(p)) => {
+                let turn = match s {
+                    GlobalState::PlayGame(PlayGameState::OurTurn(_)) => "It is your turn.",
+                    _ => "Waiting for the other players to play.",
+                };
+                let map = p.curr().map;
+                let characters = &p.curr().game_state.characters;
+                let defeated = &p.curr().progress.defeated;
+                let mut lines = vec![format!("Map {}. {}", map.name, turn)];
+                if let Some(me) = characters.get(p.curr().id) {
+                    lines.push(format!(
+                        "You are {} at {}.",
+                        me.name,
+                        coordinates(map, me.position)
+                    ));
+                    for (id, other) in characters.iter() {
+                        if id != p.curr().id {
+                            let mut line = describe_character(g, map, me, other);
+                            if defeated.contains(&id) {
+                                line.push_str(" Defeated.");
+                            }
+                            lines.push(line);
+                        }
+                    }
+                }
+                let options = match s {
+                    GlobalState::PlayGame(PlayGameState::OurTurn(_)) => {
+                        let actions = legal_actions(p.curr());
+                        actions
+                            .iter()
+                            .map(|action| describe_action(map, characters, action))
+                            .collect()
+                    }
+                    _ => vec![],
+                };
+                (lines.join("\n"), options)
+            }
+            GlobalState::GameOver(o) => {
+                let winner = o
+                    .curr()
+                    .map
+                    .teams
+                    .get(o.curr().winner.raw())
+                    .map_or("?", |(name, _)| name.as_str());
+                let outcome = if o.curr().won { "You won" } else { "You lost" };
+                (
+                    format!("The game is over. {}, team {} wins.", outcome, winner),
+                    vec!["Back to the start".to_owned()],
+                )
+            }
+            GlobalState::ConfirmExit(_) => (
+                "Do you really want to quit?".to_owned(),
+                vec![
+                    "Quit, the game can be resumed later".to_owned(),
+                    "Stay".to_owned(),
+                ],
+            ),
+            GlobalState::Exit => (String::new(), vec![]),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Line<'l> {
+    Quit,
+    Help,
+    AddBot,
+    Sort,
+    // 1 for the first option
+    Choose(usize),
+    Text(&'l str),
+    Unknown(&'l str),
+}
+
+// What a (non empty) line means. Commands start with '/', numbers choose an option unless text
+// is expected, and a text starting with '/' is typed with two.
+fn parse(line: &str, expects_text: bool) -> Line {
+    let command = match line.strip_prefix('/') {
+        Some(command) if command.starts_with('/') => return Line::Text(command),
+        Some(command) => command,
+        None => match line.parse() {
+            Ok(n) if !expects_text => return Line::Choose(n),
+            _ => return Line::Text(line),
+        },
+    };
+    match command {
+        "q" | "quit" => Line::Quit,
+        "?" | "help" => Line::Help,
+        "bot" => Line::AddBot,
+        "sort" => Line::Sort,
+        _ => match command.parse() {
+            Ok(n) => Line::Choose(n),
+            Err(_) => Line::Unknown(line),
+        },
+    }
+}
+
+// The events which select the n-th option of the current menu
+fn choose(s: &GlobalState, n: usize) -> Vec<Event> {
+    // most menus are only browsed one option at a time
+    let browse = |current: usize| {
+        let mut events: Vec<Event> = (current..n).map(|_| Event::Right).collect();
+        events.extend((n..current).map(|_| Event::Left));
+        events.push(Event::Confirm);
+        events
+    };
+    match s {
+        GlobalState::CreateOrJoin(CreateOrJoinState::Join(_)) if n == 0 => vec![Event::Choose(0)],
+        GlobalState::CreateOrJoin(CreateOrJoinState::Join(_)) => vec![Event::Confirm],
+        GlobalState::CreateOrJoin(_) => vec![Event::Choose(n)],
+        GlobalState::ConfirmExit(_) => vec![Event::Choose(n), Event::Confirm],
+        GlobalState::SelectMap(m) => browse(m.curr().curr_id),
+        GlobalState::CreateCharacter(CreateCharacterState::Team(c)) => browse(c.curr().team_index),
+        GlobalState::CreateCharacter(CreateCharacterState::Class(c)) => {
+            browse(c.curr().class_index)
+        }
+        GlobalState::CreateCharacter(CreateCharacterState::Position(c)) => {
+            browse(c.curr().position_index)
+        }
+        GlobalState::PlayGame(PlayGameState::OurTurn(p)) => {
+            let play = p.curr();
+            let cell = legal_actions(play)
+                .get(n)
+                .and_then(|action| target(play, action));
+            match cell {
+                // confirming acts on the cell under the cursor
+                Some(cell) if cell == play.cell => vec![Event::Confirm],
+                Some(cell) => vec![Event::Cell(cell), Event::Confirm],
+                None => vec![Event::Other],
+            }
+        }
+        GlobalState::GameOver(_) => vec![Event::Choose(n)],
+        _ => vec![Event::Other],
+    }
+}
+
+fn expects_text(s: &GlobalState) -> bool {
+    text(s, "").is_some()
+}
+
+// The events which replace the text of the current text field and validate it, None when the
+// state expects no text. Validating the filter of the maps keeps it: the user then picks one of
+// the maps left.
+fn text(s: &GlobalState, line: &str) -> Option<Vec<Event>> {
+    let mut events = Vec::new();
+    let current = match s {
+        GlobalState::CreateOrJoin(CreateOrJoinState::Join(j)) => &j.curr().login,
+        GlobalState::CreateCharacter(CreateCharacterState::Name(c)) => &c.curr().name,
+        GlobalState::SelectMap(m) => {
+            events.push(Event::Filter);
+            &m.curr().filter
+        }
+        _ => return None,
+    };
+    events.extend(current.chars().map(|_| Event::Backspace));
+    events.push(Event::PrintableString(line.to_owned()));
+    events.push(Event::Confirm);
+    Some(events)
+}
+
+fn legal_actions(play: &PlayGameStateDataImpl) -> Vec<WireAction> {
+    let defeated = &play.progress.defeated;
+    rules::legal_actions(play.map, &play.game_state, defeated, play.id)
+}
+
+// The cell to put the cursor on for the action
+fn target(play: &PlayGameStateDataImpl, action: &WireAction) -> Option<Id<Cell>> {
+    let characters = &play.game_state.characters;
+    match action {
+        WireAction::Move(cell) => Some(*cell),
+        WireAction::Attack(id) => characters.get(*id).map(|c| c.position),
+        WireAction::EndTurn => characters.get(play.id).map(|c| c.position),
+    }
+}
+
+fn describe_action(map: &GameMap, characters: &IdMap<Character>, action: &WireAction) -> String {
+    match action {
+        WireAction::Move(cell) => format!("Move to {}", coordinates(map, *cell)),
+        WireAction::Attack(id) => match characters.get(*id) {
+            Some(c) => format!("Attack {} at {}", c.name, coordinates(map, c.position)),
+            None => "Attack".to_owned(),
+        },
+        WireAction::EndTurn => "End the turn".to_owned(),
+    }
+}
+
+fn coordinates(map: &GameMap, cell: Id<Cell>) -> String {
+    let (x, y) = map.id_to_xy(cell);
+    format!("({},{})", x, y)
+}
+
+// e.g. "Enemy Warrior Bob at (3,4), 2 cells north-east"
+fn describe_character(
+    g: &GameDefinition,
+    map: &GameMap,
+    me: &Character,
+    other: &Character,
+) -> String {
+    let side = if other.team == me.team {
+        "Ally"
+    } else {
+        "Enemy"
+    };
+    let class = g
+        .classes
+        .get(other.class)
+        .map_or("", |class| class.name.as_str());
+    let (x, y) = map.id_to_xy(me.position);
+    let (ox, oy) = map.id_to_xy(other.position);
+    let vertical = if oy < y {
+        "north"
+    } else if oy > y {
+        "south"
+    } else {
+        ""
+    };
+    let horizontal = if ox < x {
+        "west"
+    } else if ox > x {
+        "east"
+    } else {
+        ""
+    };
+    let direction = match (vertical, horizontal) {
+        ("", h) => h.to_owned(),
+        (v, "") => v.to_owned(),
+        (v, h) => format!("{}-{}", v, h),
+    };
+    let steps = distance(map, me.position, other.position);
+
+    format!(
+        "{} {} {} at {}, {} cell{} {}",
+        side,
+        class,
+        other.name,
+        coordinates(map, other.position),
+        steps,
+        if steps == 1 { "" } else { "s" },
+        direction
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_start_with_a_slash() {
+        assert_eq!(parse("/q", false), Line::Quit);
+        assert_eq!(parse("/quit", true), Line::Quit);
+        assert_eq!(parse("/?", true), Line::Help);
+        assert_eq!(parse("/bot", false), Line::AddBot);
+        assert_eq!(parse("/sort", true), Line::Sort);
+        assert_eq!(parse("/nope", false), Line::Unknown("/nope"));
+    }
+
+    #[test]
+    fn anything_else_is_text() {
+        assert_eq!(parse("q", true), Line::Text("q"));
+        assert_eq!(parse("b", false), Line::Text("b"));
+        assert_eq!(parse("//q", true), Line::Text("/q"));
+    }
+
+    #[test]
+    fn numbers_choose_unless_text_is_expected() {
+        assert_eq!(parse("2", false), Line::Choose(2));
+        assert_eq!(parse("2", true), Line::Text("2"));
+        assert_eq!(parse("/2", true), Line::Choose(2));
+        assert_eq!(parse("/2", false), Line::Choose(2));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::api::WireAction;
+use crate::grid::{distance, neighbours};
+use pewcraft_common::game::{Cell, Character, GameMap, GameState, Id};
+
+// What a character may do during its turn, as far as the client can tell: the server has the
+// final word (e.g. it refuses everything when it is not our turn).
+
+pub fn occupant(state: &GameState, cell: Id<Cell>) -> Option<Id<Character>> {
+    state
+        .characters
+        .iter()
+        .find(|(_, c)| c.position == cell)
+        .map(|(id, _)| id)
+}
+
+// Whether a character can stand on the cell: anything on the map but walls
+pub fn walkable(map: &GameMap, cell: Id<Cell>) -> bool {
+    map.data
+        .get(cell.raw())
+        .map_or(false, |cell| !matches!(cell, Cell::Wall { .. }))
+}
+
+// Moves to a free walkable neighbouring cell, attacks on a neighbouring enemy still standing,
+// and ending the turn. Nothing for a defeated character.
+pub fn legal_actions(
+    map: &GameMap,
+    state: &GameState,
+    defeated: &[Id<Character>],
+    id: Id<Character>,
+) -> Vec<WireAction> {
+    let me = match state.characters.get(id) {
+        Some(me) if !defeated.contains(&id) => me,
+        _ => return Vec::new(),
+    };
+
+    let mut actions: Vec<WireAction> = neighbours(map, me.position)
+        .into_iter()
+        .filter(|cell| walkable(map, *cell) && occupant(state, *cell).is_none())
+        .map(WireAction::Move)
+        .collect();
+    actions.extend(
+        state
+            .characters
+            .iter()
+            .filter(|(id, c)| c.team != me.team && !defeated.contains(id))
+            .filter(|(_, c)| distance(map, me.position, c.position) == 1)
+            .map(|(id, _)| WireAction::Attack(id)),
+    );
+    actions.push(WireAction::EndTurn);
+    actions
+}
+
+// The legal action aimed at the cell: moving there, attacking who stands there, or ending the
+// turn on our own cell
+pub fn action_on(
+    map: &GameMap,
+    state: &GameState,
+    defeated: &[Id<Character>],
+    id: Id<Character>,
+    cell: Id<Cell>,
+) -> Option<WireAction> {
+    let position = state.characters.get(id)?.position;
+    legal_actions(map, state, defeated, id)
+        .into_iter()
+        .find(|action| match action {
+            WireAction::Move(target) => *target == cell,
+            WireAction::Attack(target) => {
+                state.characters.get(*target).map(|c| c.position) == Some(cell)
+            }
+            WireAction::EndTurn => position == cell,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // A corridor with a wall in its middle, a character on each side of it
+    fn corridor() -> (GameMap, GameState) {
+        let map = serde_json::from_value(json!({
+            "name": "Corridor",
+            "width": 3,
+            "height": 2,
+            "data": ["Floor", "Wall", "Floor", "Floor", "Floor", "Floor"],
+            "teams": [["Red", [0]], ["Blue", [2]]]
+        }))
+        .unwrap();
+        let state = serde_json::from_value(json!({
+            "map": 0,
+            "characters": [
+                {"name": "Alice", "class": 0, "team": 0, "position": 0},
+                {"name": "Bob", "class": 0, "team": 1, "position": 2}
+            ]
+        }))
+        .unwrap();
+        (map, state)
+    }
+
+    fn moves(actions: &[WireAction]) -> Vec<usize> {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                WireAction::Move(cell) => Some(cell.raw()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn walls_are_not_walkable() {
+        let (map, _) = corridor();
+        assert!(walkable(&map, Id::new(0)));
+        assert!(!walkable(&map, Id::new(1)));
+        // nor is anything off the map
+        assert!(!walkable(&map, Id::new(6)));
+    }
+
+    #[test]
+    fn characters_do_not_move_into_walls() {
+        let (map, state) = corridor();
+        let actions = legal_actions(&map, &state, &[], Id::new(0));
+        assert_eq!(moves(&actions), vec![3]);
+        assert!(matches!(actions.last(), Some(WireAction::EndTurn)));
+    }
+
+    #[test]
+    fn defeated_characters_cannot_do_anything() {
+        let (map, state) = corridor();
+        assert!(legal_actions(&map, &state, &[Id::new(0)], Id::new(0)).is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::config;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+const SESSION_FILE: &str = "session.json";
+
+// The character we left a game with, so that we can come back to it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub server: String,
+    pub game_id: String,
+    pub login: String,
+}
+
+impl Session {
+    // The "game_id/login" string expected by the JOIN screen
+    pub fn full_login(&self) -> String {
+        format!("{}/{}", self.game_id, self.login)
+    }
+
+    pub fn save(&self) {
+        match config::write_data(SESSION_FILE, self) {
+            Ok(()) => info!("Session stored: {:?}", self),
+            Err(e) => warn!("Could not store the session: {}", e),
+        }
+    }
+
+    // Forgets the last session, e.g. once its game is over
+    pub fn clear() {
+        match config::remove_data(SESSION_FILE) {
+            Ok(()) => info!("Session cleared"),
+            Err(e) => warn!("Could not clear the session: {}", e),
+        }
+    }
+
+    // Returns the last session on the given server, if any
+    pub fn load(server: &str) -> Option<Session> {
+        match config::read_data::<Session>(SESSION_FILE) {
+            Ok(Some(session)) if session.server == server => Some(session),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Could not load the last session: {}", e);
+                None
+            }
+        }
+    }
+}
@@ -1,15 +1,23 @@
-use crate::api;
-use log::debug;
+use crate::api::{self, WireAction, WireProgress};
+use crate::bot::{self, Bot};
+use crate::favorites::Favorites;
+use crate::grid;
+use crate::rules;
+use crate::session::Session;
+use log::{debug, warn};
 use pewcraft_common::game::{Cell, Character, Class, GameDefinition, GameMap, GameState, Id, Team};
 use pewcraft_common::io::{
     WireCreatedChar, WireCreatedGame, WireNewCharRequest, WireNewGameRequest,
 };
+use std::cmp::Ordering;
 
 #[derive(Debug)]
 pub enum Event {
     Timeout,
     PrintableString(String),
     Exit,
+    // quit right away, as if quitting was confirmed (e.g. the input was closed)
+    Quit,
     Left,
     Right,
     Up,
@@ -17,6 +25,18 @@ pub enum Event {
     Backspace,
     Cancel,
     Confirm,
+    // direct selection of the n-th option (e.g. with the mouse)
+    Choose(usize),
+    // direct selection of a cell of the map
+    Cell(Id<Cell>),
+    // fill a slot of the game with a bot
+    AddBot,
+    // change the order of a list
+    Sort,
+    // mark or unmark the selected option as a favorite
+    Favorite,
+    // start typing a filter for a list
+    Filter,
     Other,
 }
 
@@ -26,6 +46,8 @@ pub enum ExpectedEvent {
     SelectionVertical,
     SelectionHorizontal,
     Selection,
+    // choosing the target of a skill on the map
+    Target,
     None,
 }
 
@@ -66,6 +88,8 @@ pub enum GlobalState<'a> {
     WaitForGameCreation(WaitForGameCreationData<'a>),
     CreateCharacter(CreateCharacterState<'a>),
     PlayGame(PlayGameState<'a>),
+    GameOver(GameOverData<'a>),
+    ConfirmExit(ConfirmExitData<'a>),
     Exit,
 }
 
@@ -73,6 +97,8 @@ pub enum GlobalState<'a> {
 pub struct GlobalStateData<'a> {
     pub game: &'a GameDefinition,
     endpoint: &'a api::Endpoint,
+    // where bots added to the game from the lobby play
+    pub bots: &'a bot::Pool<'a>,
 }
 
 impl<'a> State for GlobalState<'a> {
@@ -81,21 +107,77 @@ impl<'a> State for GlobalState<'a> {
     fn expected_event(&self) -> ExpectedEvent {
         match self {
             GlobalState::CreateOrJoin(s) => s.expected_event(),
-            GlobalState::SelectMap(_) => ExpectedEvent::SelectionHorizontal,
+            GlobalState::SelectMap(s) if s.curr().filtering => ExpectedEvent::Char,
+            GlobalState::SelectMap(_) => ExpectedEvent::SelectionVertical,
             GlobalState::WaitForGameCreation(_) => ExpectedEvent::None,
             GlobalState::CreateCharacter(s) => s.expected_event(),
             GlobalState::PlayGame(s) => s.expected_event(),
+            GlobalState::GameOver(_) => ExpectedEvent::None,
+            GlobalState::ConfirmExit(_) => ExpectedEvent::SelectionHorizontal,
             GlobalState::Exit => ExpectedEvent::None,
         }
     }
 
     fn next(self, i: Event) -> Self::RootState {
         match (self, i) {
+            (GlobalState::ConfirmExit(c), Event::Quit) => {
+                let prev = *c.split().0;
+                prev.leave()
+            }
+            (GlobalState::ConfirmExit(c), i) => GlobalState::confirm_exit(c, i),
+            (s, Event::Quit) if s.exit_needs_confirmation() => s.leave(),
+            (_, Event::Quit) => GlobalState::Exit,
+            (s, Event::Exit) if s.exit_needs_confirmation() => GlobalState::ConfirmExit(
+                ConfirmExitData::new(Box::new(s), ConfirmExitDataImpl { leave: false }),
+            ),
             (_, Event::Exit) => GlobalState::Exit,
             unchanged @ (_, Event::Other) => unchanged.0,
 
             /* SelectMap */
-            (GlobalState::SelectMap(mut s), Event::Right) => {
+            // the filter is only typed in after asking for it, other keys are commands otherwise
+            (GlobalState::SelectMap(mut s), Event::Filter) => {
+                s.curr_mut().filtering = true;
+                GlobalState::SelectMap(s)
+            }
+            (GlobalState::SelectMap(mut s), Event::PrintableString(string))
+                if s.curr().filtering =>
+            {
+                let game = s.prev().game;
+                s.curr_mut().filter.push_str(&string);
+                s.curr_mut().refresh(game);
+                GlobalState::SelectMap(s)
+            }
+            (GlobalState::SelectMap(mut s), Event::Backspace) if s.curr().filtering => {
+                let game = s.prev().game;
+                s.curr_mut().filter.pop();
+                s.curr_mut().refresh(game);
+                GlobalState::SelectMap(s)
+            }
+            // enter keeps the filter, esc drops it
+            (GlobalState::SelectMap(mut s), Event::Confirm) if s.curr().filtering => {
+                s.curr_mut().filtering = false;
+                GlobalState::SelectMap(s)
+            }
+            (GlobalState::SelectMap(mut s), Event::Cancel) if s.curr().filtering => {
+                let game = s.prev().game;
+                s.curr_mut().filtering = false;
+                s.curr_mut().filter.clear();
+                s.curr_mut().refresh(game);
+                GlobalState::SelectMap(s)
+            }
+            // nothing to move to nor to choose when the filter matches no map
+            (GlobalState::SelectMap(s), Event::Right)
+            | (GlobalState::SelectMap(s), Event::Down)
+            | (GlobalState::SelectMap(s), Event::Left)
+            | (GlobalState::SelectMap(s), Event::Up)
+            | (GlobalState::SelectMap(s), Event::Favorite)
+            | (GlobalState::SelectMap(s), Event::Confirm)
+                if s.curr().map_ids.is_empty() =>
+            {
+                GlobalState::SelectMap(s)
+            }
+            (GlobalState::SelectMap(mut s), Event::Right)
+            | (GlobalState::SelectMap(mut s), Event::Down) => {
                 if s.curr().curr_id == s.curr().map_ids.len() - 1 {
                     s.curr_mut().curr_id = 0
                 } else {
@@ -103,7 +185,8 @@ impl<'a> State for GlobalState<'a> {
                 }
                 GlobalState::SelectMap(s)
             }
-            (GlobalState::SelectMap(mut s), Event::Left) => {
+            (GlobalState::SelectMap(mut s), Event::Left)
+            | (GlobalState::SelectMap(mut s), Event::Up) => {
                 if s.curr().curr_id == 0 {
                     s.curr_mut().curr_id = s.curr().map_ids.len() - 1;
                 } else {
@@ -111,23 +194,77 @@ impl<'a> State for GlobalState<'a> {
                 }
                 GlobalState::SelectMap(s)
             }
+            (GlobalState::SelectMap(s), Event::Cancel) => {
+                GlobalState::new(s.prev().game, s.prev().endpoint, s.prev().bots)
+            }
+            (GlobalState::SelectMap(mut s), Event::Sort) => {
+                let game = s.prev().game;
+                s.curr_mut().sort = s.curr().sort.next();
+                s.curr_mut().refresh(game);
+                GlobalState::SelectMap(s)
+            }
+            (GlobalState::SelectMap(mut s), Event::Favorite) => {
+                let game = s.prev().game;
+                let map_id = s.curr().map_ids[s.curr().curr_id];
+                let map = game.maps.get(map_id).unwrap();
+                s.curr_mut().favorites.toggle(map);
+                s.curr().favorites.save();
+                s.curr_mut().refresh(game);
+                GlobalState::SelectMap(s)
+            }
+            unchanged @ (GlobalState::SelectMap(_), Event::PrintableString(_))
+            | unchanged @ (GlobalState::SelectMap(_), Event::Backspace) => unchanged.0,
             (GlobalState::SelectMap(s), Event::Confirm) => {
                 let map_id = *s.curr().map_ids.get(s.curr().curr_id).unwrap();
                 // TODO hardcoded team size
-                // TODO this can fail :)
                 let request = WireNewGameRequest {
                     map: map_id,
                     team_size: 2,
                 };
-                let created_game = s.prev().endpoint.create_game(request);
-                let map = s.prev().game.maps.get(map_id).unwrap();
-                GlobalState::join_game(created_game, map, s)
+                match s.prev().endpoint.create_game(request) {
+                    Ok(created_game) => {
+                        let map = s.prev().game.maps.get(map_id).unwrap();
+                        GlobalState::join_game(created_game, map, s)
+                    }
+                    Err(e) => {
+                        warn!("Could not create the game: {}", e);
+                        GlobalState::SelectMap(s)
+                    }
+                }
+            }
+
+            (GlobalState::WaitForGameCreation(w), Event::AddBot) => {
+                let (settings, problem) = bot::Settings::load();
+                if let Some(problem) = problem {
+                    warn!("Bot settings: {}", problem);
+                }
+                let global = w.prev();
+                let game_id = &w.curr().game_id;
+                match Bot::join(global.endpoint, global.game, game_id, settings.difficulty) {
+                    Ok(bot) => global.bots.add(bot),
+                    Err(e) => warn!("Could not add a bot: {}", e),
+                }
+                GlobalState::WaitForGameCreation(w)
             }
+            (s, Event::Timeout) => s.refresh(),
 
+            (GlobalState::GameOver(g), Event::Confirm)
+            | (GlobalState::GameOver(g), Event::Cancel)
+            | (GlobalState::GameOver(g), Event::Choose(0)) => {
+                let global = g.split().0;
+                GlobalState::new(global.game, global.endpoint, global.bots)
+            }
+            unchanged @ (GlobalState::GameOver(_), _) => unchanged.0,
+
+            (GlobalState::CreateOrJoin(c), i) => c.next(i),
             (GlobalState::CreateCharacter(c), i) => c.next(i),
+            (GlobalState::PlayGame(p), i) => p.next(i),
             unchanged @ (GlobalState::WaitForGameCreation(_), _) => unchanged.0,
 
-            unchanged @ (_, Event::Timeout) => unchanged.0,
+            unchanged @ (_, Event::Choose(_)) | unchanged @ (_, Event::Cell(_)) => unchanged.0,
+            unchanged @ (_, Event::AddBot) => unchanged.0,
+            unchanged @ (_, Event::Sort) | unchanged @ (_, Event::Favorite) => unchanged.0,
+            unchanged @ (_, Event::Filter) => unchanged.0,
             (s, i) => {
                 panic!("Input: {:?}\nState: {:?}", i, s);
             }
@@ -156,16 +293,209 @@ impl<'a> GlobalState<'a> {
                     Some(format!("{}/{}", c.curr().game_id, c.curr().login))
                 }
             },
+            GlobalState::GameOver(g) => Some(g.curr().game_id.clone()),
+            GlobalState::ConfirmExit(c) => c.prev().get_game_id(),
             GlobalState::Exit => unreachable!(),
         }
     }
 
+    // Name of the current state, e.g. "CreateCharacter::Team"
+    pub fn variant(&self) -> &'static str {
+        match self {
+            GlobalState::CreateOrJoin(CreateOrJoinState::Create(_)) => "CreateOrJoin::Create",
+            GlobalState::CreateOrJoin(CreateOrJoinState::Join(_)) => "CreateOrJoin::Join",
+            GlobalState::SelectMap(_) => "SelectMap",
+            GlobalState::WaitForGameCreation(_) => "WaitForGameCreation",
+            GlobalState::CreateCharacter(CreateCharacterState::Team(_)) => "CreateCharacter::Team",
+            GlobalState::CreateCharacter(CreateCharacterState::Class(_)) => {
+                "CreateCharacter::Class"
+            }
+            GlobalState::CreateCharacter(CreateCharacterState::Position(_)) => {
+                "CreateCharacter::Position"
+            }
+            GlobalState::CreateCharacter(CreateCharacterState::Name(_)) => "CreateCharacter::Name",
+            GlobalState::PlayGame(PlayGameState::OurTurn(_)) => "PlayGame::OurTurn",
+            GlobalState::PlayGame(PlayGameState::NotOurTurn(_)) => "PlayGame::NotOurTurn",
+            GlobalState::GameOver(_) => "GameOver",
+            GlobalState::ConfirmExit(_) => "ConfirmExit",
+            GlobalState::Exit => "Exit",
+        }
+    }
+
     pub fn exit(&self) -> bool {
         matches!(self, GlobalState::Exit)
     }
 
-    pub fn new(game: &'a GameDefinition, endpoint: &'a api::Endpoint) -> Self {
-        let global_state_data = GlobalStateData { game, endpoint };
+    // Quitting right away is only fine as long as we have nothing to lose
+    fn exit_needs_confirmation(&self) -> bool {
+        matches!(
+            self,
+            GlobalState::CreateCharacter(_)
+                | GlobalState::WaitForGameCreation(_)
+                | GlobalState::PlayGame(_)
+        )
+    }
+
+    // Asks the server how the game we are in goes on: whether it started, whose turn it is and
+    // whether it is over
+    fn refresh(self) -> GlobalState<'a> {
+        let (global, game_id) = match &self {
+            GlobalState::WaitForGameCreation(w) => (w.prev(), &w.curr().game_id),
+            GlobalState::PlayGame(PlayGameState::OurTurn(p))
+            | GlobalState::PlayGame(PlayGameState::NotOurTurn(p)) => (p.prev(), &p.curr().game_id),
+            _ => return self,
+        };
+        let (progress, game_state) = match global.endpoint.poll_game(game_id) {
+            Ok((progress, Some(game_state))) => (progress, game_state),
+            Err(e) => {
+                // polled on every tick: the connection indicator already tells the user
+                debug!("Could not refresh game {}: {}", game_id, e);
+                return self;
+            }
+            Ok((_, None)) => {
+                warn!("Game {} does not exist anymore", game_id);
+                return self;
+            }
+        };
+
+        match self {
+            GlobalState::WaitForGameCreation(w) if progress.turn.is_some() => {
+                let (global, wait) = w.split();
+                let cell = game_state
+                    .characters
+                    .get(wait.id)
+                    .map_or(Id::new(0), |me| me.position);
+                GlobalState::play(
+                    global,
+                    PlayGameStateDataImpl {
+                        cell,
+                        game_state,
+                        progress,
+                        map: wait.map,
+                        game_id: wait.game_id,
+                        login: wait.login,
+                        id: wait.id,
+                    },
+                )
+            }
+            GlobalState::PlayGame(PlayGameState::OurTurn(p))
+            | GlobalState::PlayGame(PlayGameState::NotOurTurn(p)) => {
+                let (global, mut play) = p.split();
+                play.game_state = game_state;
+                play.progress = progress;
+                GlobalState::play(global, play)
+            }
+            s => s,
+        }
+    }
+
+    fn play(global: GlobalStateData<'a>, play: PlayGameStateDataImpl<'a>) -> GlobalState<'a> {
+        if let Some(winner) = play.progress.winner {
+            // nothing to come back to anymore
+            Session::clear();
+            let team = play.game_state.characters.get(play.id).map(|me| me.team);
+            return GlobalState::GameOver(GameOverData::new(
+                global,
+                GameOverDataImpl {
+                    map: play.map,
+                    game_id: play.game_id,
+                    winner,
+                    won: team == Some(winner),
+                },
+            ));
+        }
+
+        let data = PlayGameStateData::new(global, play);
+        if data.curr().progress.turn == Some(data.curr().id) {
+            GlobalState::PlayGame(PlayGameState::OurTurn(data))
+        } else {
+            GlobalState::PlayGame(PlayGameState::NotOurTurn(data))
+        }
+    }
+
+    fn confirm_exit(mut c: ConfirmExitData<'a>, i: Event) -> GlobalState<'a> {
+        match i {
+            Event::Left | Event::Right | Event::Up | Event::Down => {
+                c.curr_mut().leave = !c.curr().leave;
+                GlobalState::ConfirmExit(c)
+            }
+            Event::Choose(0) => {
+                c.curr_mut().leave = true;
+                GlobalState::ConfirmExit(c)
+            }
+            Event::Choose(1) => {
+                c.curr_mut().leave = false;
+                GlobalState::ConfirmExit(c)
+            }
+            Event::Confirm if c.curr().leave => {
+                let prev = *c.split().0;
+                prev.leave()
+            }
+            // quitting twice in a row is explicit enough
+            Event::Exit => {
+                let prev = *c.split().0;
+                prev.leave()
+            }
+            Event::Confirm | Event::Cancel => *c.split().0,
+            _ => GlobalState::ConfirmExit(c),
+        }
+    }
+
+    // Leaves the current game (if any) gracefully, keeping what is needed to resume it later
+    fn leave(self) -> GlobalState<'a> {
+        let (global, game_id, login) = match &self {
+            GlobalState::WaitForGameCreation(w) => (w.prev(), &w.curr().game_id, &w.curr().login),
+            GlobalState::PlayGame(PlayGameState::OurTurn(p))
+            | GlobalState::PlayGame(PlayGameState::NotOurTurn(p)) => {
+                (p.prev(), &p.curr().game_id, &p.curr().login)
+            }
+            // the creation of a character was abandoned, there is nothing to come back to
+            _ => {
+                Session::clear();
+                return GlobalState::Exit;
+            }
+        };
+
+        global.endpoint.leave_game(game_id, login);
+        Session {
+            server: global.endpoint.url().to_owned(),
+            game_id: game_id.clone(),
+            login: login.clone(),
+        }
+        .save();
+        GlobalState::Exit
+    }
+
+    // Starts on the JOIN screen, with the login of a previous session already typed in
+    pub fn resume(
+        game: &'a GameDefinition,
+        endpoint: &'a api::Endpoint,
+        bots: &'a bot::Pool<'a>,
+        session: Session,
+    ) -> Self {
+        let global_state_data = GlobalStateData {
+            game,
+            endpoint,
+            bots,
+        };
+        GlobalState::CreateOrJoin(CreateOrJoinState::Join(CreateOrJoinData::new(
+            global_state_data,
+            CreateOrJoinDataImpl {
+                login: session.full_login(),
+            },
+        )))
+    }
+
+    pub fn new(
+        game: &'a GameDefinition,
+        endpoint: &'a api::Endpoint,
+        bots: &'a bot::Pool<'a>,
+    ) -> Self {
+        let global_state_data = GlobalStateData {
+            game,
+            endpoint,
+            bots,
+        };
         GlobalState::CreateOrJoin(CreateOrJoinState::Create(CreateOrJoinData::new(
             global_state_data,
             CreateOrJoinDataImpl {
@@ -178,6 +508,14 @@ impl<'a> GlobalState<'a> {
         created_game: WireCreatedGame,
         map: &'a GameMap,
         s: SelectMapData<'a>,
+    ) -> GlobalState<'a> {
+        GlobalState::create_character(s.split().0, created_game, map)
+    }
+
+    fn create_character(
+        global: GlobalStateData<'a>,
+        created_game: WireCreatedGame,
+        map: &'a GameMap,
     ) -> GlobalState<'a> {
         let create_character_state_data = CreateCharacterStateDataImpl {
             name: String::new(),
@@ -185,7 +523,7 @@ impl<'a> GlobalState<'a> {
             team_index: 0,
             position_index: 0,
 
-            classes: s.prev().game.classes.ids(),
+            classes: global.game.classes.ids(),
             teams: map
                 .teams
                 .iter()
@@ -196,10 +534,33 @@ impl<'a> GlobalState<'a> {
             game_id: created_game.game_id,
         };
 
-        let state_data = StateData::new(s.split().0, create_character_state_data);
+        let state_data = StateData::new(global, create_character_state_data);
         let create_character_state = CreateCharacterState::Team(state_data);
         GlobalState::CreateCharacter(create_character_state)
     }
+
+    // Comes back to a game with a character which left it, returns the character and the map
+    fn rejoin_game(
+        global: &GlobalStateData<'a>,
+        game_id: &str,
+        login: &str,
+    ) -> Result<(Id<Character>, &'a GameMap), String> {
+        let id = global
+            .endpoint
+            .rejoin_game(game_id, login)
+            .map_err(|e| e.to_string())?;
+        let state = global
+            .endpoint
+            .game_state(game_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("no game {}", game_id))?;
+        let map = global
+            .game
+            .maps
+            .get(state.map)
+            .ok_or_else(|| format!("unknown map {}", state.map.raw()))?;
+        Ok((id, map))
+    }
 }
 
 #[derive(Debug)]
@@ -236,63 +597,69 @@ impl<'a> State for CreateOrJoinState<'a> {
                 s.curr_mut().login.push_str(&string);
                 GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
             }
+            (CreateOrJoinState::Join(mut s), Event::Backspace) => {
+                s.curr_mut().login.pop();
+                GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
+            }
             (CreateOrJoinState::Join(s), Event::Cancel) => {
                 GlobalState::CreateOrJoin(CreateOrJoinState::Create(s))
             }
             (CreateOrJoinState::Join(s), Event::Confirm) => {
-                let (global, join) = s.split();
-                let login = &join.login;
-                match login.len() {
-                    10 => {
-                        let joined_game = global.endpoint.join_game(login);
-                        match joined_game {
-                            None => GlobalState::CreateOrJoin(CreateOrJoinState::Create(
-                                CreateOrJoinData::new(global, join),
-                            )),
-                            Some(game_info) => {
-                                let map = global.game.maps.get(game_info.map).unwrap();
-                                let create_character_state_data = CreateCharacterStateDataImpl {
-                                    name: String::new(),
-                                    class_index: 0,
-                                    team_index: 0,
-                                    position_index: 0,
-
-                                    classes: global.game.classes.ids(),
-                                    teams: map
-                                        .teams
-                                        .iter()
-                                        .enumerate()
-                                        .map(|(index, _)| Id::new(index))
-                                        .collect(),
-                                    map,
-                                    game_id: game_info.game_id,
-                                };
-                                let state_data =
-                                    StateData::new(global, create_character_state_data);
-                                let create_character_state = CreateCharacterState::Team(state_data);
-                                GlobalState::CreateCharacter(create_character_state)
+                // either "game id", to create a character in that game, or "game id/login" to
+                // come back with a character
+                let login = s.curr().login.trim().to_owned();
+                match login.split_once('/') {
+                    Some((game_id, login)) => {
+                        let rejoined = GlobalState::rejoin_game(s.prev(), game_id, login);
+                        match rejoined {
+                            Ok((id, map)) => {
+                                // the session was used, it is stored again when leaving
+                                Session::clear();
+                                GlobalState::WaitForGameCreation(WaitForGameCreationData::new(
+                                    s.split().0,
+                                    WaitForGameCreationDataImpl {
+                                        map,
+                                        game_id: game_id.to_owned(),
+                                        login: login.to_owned(),
+                                        id,
+                                    },
+                                ))
+                            }
+                            Err(e) => {
+                                warn!("Could not rejoin game {}: {}", game_id, e);
+                                GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
                             }
                         }
                     }
-                    21 => {
-                        if login.chars().nth(10).unwrap() != '/' {
-                            GlobalState::CreateOrJoin(CreateOrJoinState::Create(
-                                CreateOrJoinData::new(global, join),
-                            ))
-                        } else {
-                            let game_id = &login[..10];
-                            let char_id = &login[11..];
-                            let joined_game = global.endpoint.join_game_with_char(game_id, char_id);
-                            unimplemented!()
-                            //match joined_game
+                    None => match s.prev().endpoint.join_game(&login) {
+                        Ok(Some(joined)) => match s.prev().game.maps.get(joined.map) {
+                            Some(map) => GlobalState::create_character(s.split().0, joined, map),
+                            None => {
+                                warn!("Game {} is played on an unknown map", login);
+                                GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
+                            }
+                        },
+                        Ok(None) => {
+                            warn!("There is no game {}", login);
+                            GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
                         }
-                    }
-                    _ => GlobalState::CreateOrJoin(CreateOrJoinState::Create(
-                        CreateOrJoinData::new(global, join),
-                    )),
+                        Err(e) => {
+                            warn!("Could not join game {}: {}", login, e);
+                            GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
+                        }
+                    },
                 }
             }
 
+            (CreateOrJoinState::Create(s), Event::Choose(0))
+            | (CreateOrJoinState::Join(s), Event::Choose(0)) => {
+                CreateOrJoinState::Create(s).next(Event::Confirm)
+            }
+            (CreateOrJoinState::Create(s), Event::Choose(1))
+            | (CreateOrJoinState::Join(s), Event::Choose(1)) => {
+                GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
+            }
+
             (CreateOrJoinState::Create(s), Event::Right)
             | (CreateOrJoinState::Create(s), Event::Up)
             | (CreateOrJoinState::Create(s), Event::Down)
@@ -304,11 +671,9 @@ impl<'a> State for CreateOrJoinState<'a> {
                 let global_state_data = GlobalStateData {
                     game: global.game,
                     endpoint: global.endpoint,
+                    bots: global.bots,
                 };
-                let select_map_state_data = SelectMapDataImpl {
-                    map_ids: global_state_data.game.maps.ids(),
-                    curr_id: 0,
-                };
+                let select_map_state_data = SelectMapDataImpl::new(global_state_data.game);
 
                 GlobalState::SelectMap(SelectMapData::new(global_state_data, select_map_state_data))
             }
@@ -318,10 +683,110 @@ impl<'a> State for CreateOrJoinState<'a> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapSort {
+    // the order of the game definition
+    Definition,
+    // smallest first
+    Size,
+    // most teams first
+    Teams,
+}
+
+impl MapSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            MapSort::Definition => "default",
+            MapSort::Size => "size",
+            MapSort::Teams => "teams",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            MapSort::Definition => MapSort::Size,
+            MapSort::Size => MapSort::Teams,
+            MapSort::Teams => MapSort::Definition,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SelectMapDataImpl {
+    // the maps matching the filter, favorites first, in the chosen order
     pub map_ids: Vec<Id<GameMap>>,
     pub curr_id: usize,
+    // part of the name of the maps shown, whatever the case
+    pub filter: String,
+    // whether what is typed goes to the filter
+    pub filtering: bool,
+    pub sort: MapSort,
+    pub favorites: Favorites,
+}
+
+impl SelectMapDataImpl {
+    fn new(game: &GameDefinition) -> Self {
+        let mut data = SelectMapDataImpl {
+            map_ids: Vec::new(),
+            curr_id: 0,
+            filter: String::new(),
+            filtering: false,
+            sort: MapSort::Definition,
+            favorites: Favorites::load(),
+        };
+        data.refresh(game);
+        data
+    }
+
+    // Lists the maps again after a change of the filter, sort or favorites.
+    // The selected map stays selected if it is still listed.
+    fn refresh(&mut self, game: &GameDefinition) {
+        let selected = self.map_ids.get(self.curr_id).copied();
+        let entries = game
+            .maps
+            .iter()
+            .map(|(id, map)| MapEntry {
+                id,
+                name: &map.name,
+                size: map.width * map.height,
+                teams: map.teams.len(),
+                favorite: self.favorites.contains(map),
+            })
+            .collect();
+        let map_ids = list_maps(entries, &self.filter, self.sort);
+        self.curr_id = selected
+            .and_then(|selected| map_ids.iter().position(|id| *id == selected))
+            .unwrap_or(0);
+        self.map_ids = map_ids;
+    }
+}
+
+// What the list of maps is sorted and filtered on
+#[derive(Debug)]
+struct MapEntry<'m> {
+    id: Id<GameMap>,
+    name: &'m str,
+    // in cells
+    size: usize,
+    teams: usize,
+    favorite: bool,
+}
+
+// The maps whose name contains the filter (whatever the case), favorites first, then in the
+// given order. Equal maps keep the order of the game definition.
+fn list_maps(mut entries: Vec<MapEntry>, filter: &str, sort: MapSort) -> Vec<Id<GameMap>> {
+    let filter = filter.to_lowercase();
+    entries.retain(|entry| entry.name.to_lowercase().contains(&filter));
+    // a stable sort
+    entries.sort_by(|a, b| {
+        let favorites_first = b.favorite.cmp(&a.favorite);
+        favorites_first.then_with(|| match sort {
+            MapSort::Definition => Ordering::Equal,
+            MapSort::Size => a.size.cmp(&b.size),
+            MapSort::Teams => b.teams.cmp(&a.teams),
+        })
+    });
+    entries.into_iter().map(|entry| entry.id).collect()
 }
 pub type SelectMapData<'a> = StateData<GlobalStateData<'a>, SelectMapDataImpl>;
 
@@ -410,6 +875,20 @@ impl<'a> State for CreateCharacterState<'a> {
             (CreateCharacterState::Position(s), Event::Confirm) => {
                 GlobalState::CreateCharacter(CreateCharacterState::Name(s))
             }
+            (CreateCharacterState::Position(mut s), Event::Cell(cell)) => {
+                let positions = &s.curr().map.teams.get(s.curr().team_index).unwrap().1;
+                match positions.iter().position(|p| *p == cell) {
+                    // choosing the current position again confirms it
+                    Some(index) if index == s.curr().position_index => {
+                        GlobalState::CreateCharacter(CreateCharacterState::Name(s))
+                    }
+                    Some(index) => {
+                        s.curr_mut().position_index = index;
+                        GlobalState::CreateCharacter(CreateCharacterState::Position(s))
+                    }
+                    None => GlobalState::CreateCharacter(CreateCharacterState::Position(s)),
+                }
+            }
 
             // THEN THE NAME
             (CreateCharacterState::Name(mut s), Event::PrintableString(string)) => {
@@ -423,7 +902,7 @@ impl<'a> State for CreateCharacterState<'a> {
             (CreateCharacterState::Name(s), Event::Confirm) => {
                 debug!("Creating character with name {}", s.curr().name);
                 let (global, create_char) = s.split();
-                let name = create_char.name;
+                let name = create_char.name.clone();
                 let class = *create_char.classes.get(create_char.class_index).unwrap();
                 let team = *create_char.teams.get(create_char.team_index).unwrap();
                 let position = *create_char
@@ -434,15 +913,23 @@ impl<'a> State for CreateCharacterState<'a> {
                     .1
                     .get(create_char.position_index)
                     .unwrap();
-                let WireCreatedChar(login, id) = global.endpoint.create_char(
-                    &create_char.game_id,
-                    WireNewCharRequest {
-                        name,
-                        class,
-                        team,
-                        position,
-                    },
-                );
+                let request = WireNewCharRequest {
+                    name,
+                    class,
+                    team,
+                    position,
+                };
+                let WireCreatedChar(login, id) =
+                    match global.endpoint.create_char(&create_char.game_id, request) {
+                        Ok(created) => created,
+                        // e.g. someone else took the position meanwhile
+                        Err(e) => {
+                            warn!("Could not create the character: {}", e);
+                            return GlobalState::CreateCharacter(CreateCharacterState::Name(
+                                CreateCharacterStateData::new(global, create_char),
+                            ));
+                        }
+                    };
                 GlobalState::WaitForGameCreation(WaitForGameCreationData::new(
                     global,
                     WaitForGameCreationDataImpl {
@@ -483,26 +970,112 @@ impl<'a> State for PlayGameState<'a> {
     fn expected_event(&self) -> ExpectedEvent {
         match self {
             PlayGameState::NotOurTurn(_) => ExpectedEvent::None,
-            PlayGameState::OurTurn(_) => unimplemented!(),
+            PlayGameState::OurTurn(_) => ExpectedEvent::Target,
         }
     }
 
     fn next(self, i: Event) -> Self::RootState {
-        unimplemented!()
+        match (self, i) {
+            // THE CURSOR CHOOSES THE CELL TO ACT ON
+            (PlayGameState::OurTurn(mut s), Event::Left) => {
+                s.curr_mut().move_cursor(-1, 0);
+                GlobalState::PlayGame(PlayGameState::OurTurn(s))
+            }
+            (PlayGameState::OurTurn(mut s), Event::Right) => {
+                s.curr_mut().move_cursor(1, 0);
+                GlobalState::PlayGame(PlayGameState::OurTurn(s))
+            }
+            (PlayGameState::OurTurn(mut s), Event::Up) => {
+                s.curr_mut().move_cursor(0, -1);
+                GlobalState::PlayGame(PlayGameState::OurTurn(s))
+            }
+            (PlayGameState::OurTurn(mut s), Event::Down) => {
+                s.curr_mut().move_cursor(0, 1);
+                GlobalState::PlayGame(PlayGameState::OurTurn(s))
+            }
+            (PlayGameState::OurTurn(mut s), Event::Cancel) => {
+                if let Some(me) = s.curr().game_state.characters.get(s.curr().id) {
+                    s.curr_mut().cell = me.position;
+                }
+                GlobalState::PlayGame(PlayGameState::OurTurn(s))
+            }
+            // choosing the cell of the cursor again acts on it
+            (PlayGameState::OurTurn(s), Event::Cell(cell)) if cell == s.curr().cell => {
+                PlayGameState::OurTurn(s).next(Event::Confirm)
+            }
+            (PlayGameState::OurTurn(mut s), Event::Cell(cell)) => {
+                s.curr_mut().cell = cell;
+                GlobalState::PlayGame(PlayGameState::OurTurn(s))
+            }
+            (PlayGameState::OurTurn(s), Event::Confirm) => {
+                let action = match s.curr().action() {
+                    Some(action) => action,
+                    None => {
+                        debug!("Nothing to do on cell {}", s.curr().cell.raw());
+                        return GlobalState::PlayGame(PlayGameState::OurTurn(s));
+                    }
+                };
+                let (endpoint, play) = (s.prev().endpoint, s.curr());
+                let (game_id, login) = (play.game_id.as_str(), play.login.as_str());
+                if let Err(e) = endpoint.act(game_id, login, &action) {
+                    warn!("Could not play {:?}: {}", action, e);
+                    return GlobalState::PlayGame(PlayGameState::OurTurn(s));
+                }
+                // a character only acts once per turn
+                if !matches!(action, WireAction::EndTurn) {
+                    if let Err(e) = endpoint.act(game_id, login, &WireAction::EndTurn) {
+                        warn!("Could not end the turn: {}", e);
+                    }
+                }
+                GlobalState::PlayGame(PlayGameState::OurTurn(s)).refresh()
+            }
+
+            (PlayGameState::NotOurTurn(mut s), Event::Cell(cell)) => {
+                s.curr_mut().cell = cell;
+                GlobalState::PlayGame(PlayGameState::NotOurTurn(s))
+            }
+            unchanged => GlobalState::PlayGame(unchanged.0),
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct PlayGameStateDataImpl<'a> {
+    // the cursor, on the cell to act on
     pub cell: Id<Cell>,
     pub game_state: GameState,
+    pub progress: WireProgress,
     pub map: &'a GameMap,
     pub game_id: String,
     pub login: String,
     pub id: Id<Character>,
 }
+
+impl<'a> PlayGameStateDataImpl<'a> {
+    fn move_cursor(&mut self, dx: isize, dy: isize) {
+        if let Some(cell) = grid::step(self.map, self.cell, dx, dy) {
+            self.cell = cell;
+        }
+    }
+
+    // What confirming does with the cell under the cursor, None when it does nothing
+    pub fn action(&self) -> Option<WireAction> {
+        let defeated = &self.progress.defeated;
+        rules::action_on(self.map, &self.game_state, defeated, self.id, self.cell)
+    }
+}
 pub type PlayGameStateData<'a> = StateData<GlobalStateData<'a>, PlayGameStateDataImpl<'a>>;
 
+#[derive(Debug)]
+pub struct GameOverDataImpl<'a> {
+    pub map: &'a GameMap,
+    pub game_id: String,
+    pub winner: Id<Team>,
+    // whether our character is in the winning team
+    pub won: bool,
+}
+pub type GameOverData<'a> = StateData<GlobalStateData<'a>, GameOverDataImpl<'a>>;
+
 #[derive(Debug)]
 pub struct WaitForGameCreationDataImpl<'a> {
     pub map: &'a GameMap,
@@ -512,3 +1085,76 @@ pub struct WaitForGameCreationDataImpl<'a> {
 }
 pub type WaitForGameCreationData<'a> =
     StateData<GlobalStateData<'a>, WaitForGameCreationDataImpl<'a>>;
+
+#[derive(Debug)]
+pub struct ConfirmExitDataImpl {
+    pub leave: bool,
+}
+pub type ConfirmExitData<'a> = StateData<Box<GlobalState<'a>>, ConfirmExitDataImpl>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: usize, name: &str, size: usize, teams: usize, favorite: bool) -> MapEntry {
+        MapEntry {
+            id: Id::new(id),
+            name,
+            size,
+            teams,
+            favorite,
+        }
+    }
+
+    fn maps() -> Vec<MapEntry<'static>> {
+        vec![
+            entry(0, "Arena", 100, 2, false),
+            entry(1, "Small arena", 25, 4, false),
+            entry(2, "Desert", 400, 2, true),
+            entry(3, "Islands", 25, 3, false),
+        ]
+    }
+
+    fn ids(ids: &[usize]) -> Vec<Id<GameMap>> {
+        ids.iter().map(|id| Id::new(*id)).collect()
+    }
+
+    #[test]
+    fn favorites_come_first_in_the_order_of_the_definition() {
+        assert_eq!(
+            list_maps(maps(), "", MapSort::Definition),
+            ids(&[2, 0, 1, 3])
+        );
+    }
+
+    #[test]
+    fn filters_on_the_name_whatever_the_case() {
+        assert_eq!(
+            list_maps(maps(), "ARENA", MapSort::Definition),
+            ids(&[0, 1])
+        );
+        assert_eq!(list_maps(maps(), "sert", MapSort::Definition), ids(&[2]));
+        assert!(list_maps(maps(), "nowhere", MapSort::Definition).is_empty());
+    }
+
+    #[test]
+    fn sorts_by_size_smallest_first() {
+        // equal sizes keep the order of the definition
+        assert_eq!(list_maps(maps(), "", MapSort::Size), ids(&[2, 1, 3, 0]));
+    }
+
+    #[test]
+    fn sorts_by_teams_most_first() {
+        assert_eq!(list_maps(maps(), "", MapSort::Teams), ids(&[2, 1, 3, 0]));
+        let no_favorite = maps().into_iter().filter(|m| !m.favorite).collect();
+        assert_eq!(list_maps(no_favorite, "", MapSort::Teams), ids(&[1, 3, 0]));
+    }
+
+    #[test]
+    fn sorts_cycle_through_every_order() {
+        let sort = MapSort::Definition;
+        assert_eq!(sort.next(), MapSort::Size);
+        assert_eq!(sort.next().next(), MapSort::Teams);
+        assert_eq!(sort.next().next().next(), MapSort::Definition);
+    }
+}
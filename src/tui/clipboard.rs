@@ -0,0 +1,152 @@
+#[cfg(feature = "clipboard")]
+use clipboard::{ClipboardContext, ClipboardProvider};
+use log::{info, warn};
+use std::env;
+use std::io::Write;
+
+// Where copied strings end up.
+// The system clipboard is preferred; without one (SSH session, CI, no X11...) we emit OSC 52 escape
+// sequences so that the *local* terminal emulator stores the string, and keep a copy in-process
+// so that pasting inside the client still works.
+// Without the "clipboard" feature, the system clipboard is never used.
+pub enum Clipboard {
+    #[cfg(feature = "clipboard")]
+    System(ClipboardContext),
+    Osc52(String),
+    Buffer(String),
+}
+
+impl Clipboard {
+    #[cfg(feature = "clipboard")]
+    pub fn new() -> Self {
+        match ClipboardContext::new() {
+            Ok(ctx) => {
+                info!("Using the system clipboard");
+                Clipboard::System(ctx)
+            }
+            Err(e) => {
+                info!("No system clipboard available: {}", e);
+                Clipboard::fallback()
+            }
+        }
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    pub fn new() -> Self {
+        info!("Built without the system clipboard");
+        Clipboard::fallback()
+    }
+
+    fn fallback() -> Self {
+        if Clipboard::terminal_supports_osc52() {
+            info!("Falling back to OSC 52 escape sequences");
+            Clipboard::Osc52(String::new())
+        } else {
+            info!("Falling back to an in-process buffer");
+            Clipboard::Buffer(String::new())
+        }
+    }
+
+    // Most terminals support OSC 52 nowadays, the ones that do not simply ignore the sequence.
+    // We still skip it for the dumbest terminals, which could print it as garbage.
+    fn terminal_supports_osc52() -> bool {
+        match env::var("TERM") {
+            Ok(term) => term != "dumb" && term != "linux",
+            Err(_) => false,
+        }
+    }
+
+    pub fn set<W: Write>(&mut self, out: &mut W, contents: String) {
+        match self {
+            #[cfg(feature = "clipboard")]
+            Clipboard::System(ctx) => {
+                if let Err(e) = ctx.set_contents(contents.clone()) {
+                    warn!("Could not write to the system clipboard: {}", e);
+                    *self = Clipboard::Buffer(contents);
+                }
+            }
+            Clipboard::Osc52(buffer) => {
+                if let Err(e) = write!(out, "\x1b]52;c;{}\x07", base64(contents.as_bytes()))
+                    .and_then(|_| out.flush())
+                {
+                    warn!("Could not write the OSC 52 sequence: {}", e);
+                }
+                *buffer = contents;
+            }
+            Clipboard::Buffer(buffer) => *buffer = contents,
+        }
+    }
+
+    pub fn get(&mut self) -> Option<String> {
+        match self {
+            #[cfg(feature = "clipboard")]
+            Clipboard::System(ctx) => match ctx.get_contents() {
+                Ok(contents) => Some(contents),
+                Err(e) => {
+                    warn!("Could not read from the system clipboard: {}", e);
+                    None
+                }
+            },
+            // reading OSC 52 back requires the terminal to answer a query, which is almost never
+            // allowed: only what we copied ourselves can be pasted
+            Clipboard::Osc52(buffer) | Clipboard::Buffer(buffer) => {
+                if buffer.is_empty() {
+                    None
+                } else {
+                    Some(buffer.clone())
+                }
+            }
+        }
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(input: &[u8]) -> String {
+    let mut out = String::with_capacity((input.len() + 2) / 3 * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        out.push(BASE64_ALPHABET[n >> 18 & 63] as char);
+        out.push(BASE64_ALPHABET[n >> 12 & 63] as char);
+        out.push(if chunk.len() > 1 {
+            BASE64_ALPHABET[n >> 6 & 63] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            BASE64_ALPHABET[n & 63] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 test vectors
+    #[test]
+    fn base64_matches_the_rfc() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(b"fooba"), "Zm9vYmE=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn base64_uses_the_whole_alphabet() {
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+        assert_eq!(base64("é".as_bytes()), "w6k=");
+    }
+}
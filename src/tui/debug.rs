@@ -0,0 +1,74 @@
+use crate::tui::overlay::Clear;
+use crate::tui::theme::Theme;
+use ::tui::buffer::Buffer;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::widgets::{Block, Borders, Paragraph, Text, Widget};
+use pewcraft_frontend::history;
+use pewcraft_frontend::state::{GlobalState, State};
+use std::time::Duration;
+
+const DEBUG_BLOCK_TITLE: &str = "Debug (F12 to close)";
+const SHOWN_EVENTS: usize = 10;
+const SHOWN_EXCHANGES: usize = 10;
+
+// State machine internals and network timings, drawn over the right half of the screen
+pub(super) struct DebugOverlay<'a>(
+    pub(super) &'a GlobalState<'a>,
+    pub(super) Duration,
+    pub(super) &'a Theme,
+);
+impl<'a> Widget for DebugOverlay<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let width = area.width / 2;
+        let area = Rect::new(area.right() - width, area.y, width, area.height);
+        Clear.draw(area, buf);
+
+        let theme = self.2;
+        let title = theme.title;
+        let mut text = vec![
+            Text::styled("State:          ", title),
+            Text::raw(format!("{}\n", self.0.variant())),
+            Text::styled("Expecting:      ", title),
+            Text::raw(format!("{:?}\n", self.0.expected_event())),
+            Text::styled("Frame rendered: ", title),
+            Text::raw(format!("{:?}\n", self.1)),
+            Text::styled("\nLast events:\n", title),
+        ];
+
+        let events = history::events();
+        for event in events.iter().rev().take(SHOWN_EVENTS) {
+            text.push(Text::raw(format!("  {}\n", event)));
+        }
+
+        text.push(Text::styled("\nLast requests:\n", title));
+        let exchanges = history::exchanges();
+        for exchange in exchanges.iter().rev().take(SHOWN_EXCHANGES) {
+            let (status, style) = match exchange.status {
+                Some(status) if status < 400 => (status.to_string(), theme.text),
+                Some(status) => (status.to_string(), theme.error),
+                None => ("---".to_owned(), theme.error),
+            };
+            text.push(Text::styled(
+                format!(
+                    "  {} {:>6}ms {} {}\n",
+                    status,
+                    exchange.latency.as_millis(),
+                    exchange.method,
+                    exchange.path
+                ),
+                style,
+            ));
+        }
+
+        Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title(DEBUG_BLOCK_TITLE)
+                    .title_style(theme.title)
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            )
+            .alignment(Alignment::Left)
+            .draw(area, buf);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::tui::overlay::{self, Clear};
+use crate::tui::theme::Theme;
+use ::tui::buffer::Buffer;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::widgets::{Block, Borders, Paragraph, Text, Widget};
+
+// Modal box asking the user to choose between a few options, drawn over the current screen
+pub(super) struct Dialog<'a> {
+    pub(super) title: &'a str,
+    pub(super) message: &'a str,
+    pub(super) options: &'a [&'a str],
+    pub(super) selected: usize,
+    pub(super) theme: &'a Theme,
+}
+
+impl<'a> Widget for Dialog<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let area = overlay::centered(area, 50, 30);
+        Clear.draw(area, buf);
+
+        let theme = self.theme;
+        let mut text = vec![Text::styled(format!("\n{}\n\n", self.message), theme.text)];
+        for (index, option) in self.options.iter().enumerate() {
+            if index > 0 {
+                text.push(Text::raw("    "));
+            }
+            let option = format!("[ {} ]", option);
+            text.push(if index == self.selected {
+                Text::styled(option, theme.selection)
+            } else {
+                Text::styled(option, theme.text)
+            });
+        }
+
+        Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title(self.title)
+                    .title_style(theme.title)
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            )
+            .alignment(Alignment::Center)
+            .wrap(true)
+            .draw(area, buf);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::tui::overlay::{self, Clear};
+use crate::tui::panel::{InfoPanel, Scroll};
+use crate::tui::theme::Theme;
+use ::tui::buffer::Buffer;
+use ::tui::layout::Rect;
+use ::tui::widgets::{Text, Widget};
+use pewcraft_common::game::{Class, GameDefinition, Id};
+use pewcraft_frontend::state::{GlobalState, PlayGameState};
+
+const ENCYCLOPEDIA_BLOCK_TITLE: &str =
+    "Classes (left/right to browse, enter to pin for comparison, esc to close)";
+const SHEET_BLOCK_TITLE: &str = "Character sheet (esc to close)";
+
+// Which class the encyclopedia shows, and the one it is compared to
+#[derive(Debug)]
+pub(super) struct Encyclopedia {
+    index: usize,
+    pinned: Option<usize>,
+}
+
+impl Encyclopedia {
+    pub(super) fn new() -> Self {
+        Encyclopedia {
+            index: 0,
+            pinned: None,
+        }
+    }
+
+    pub(super) fn previous(&mut self, count: usize) {
+        if count > 0 {
+            self.index = (self.index + count - 1) % count;
+        }
+    }
+
+    pub(super) fn next(&mut self, count: usize) {
+        if count > 0 {
+            self.index = (self.index + 1) % count;
+        }
+    }
+
+    // Pins the current class, or unpins the pinned one
+    pub(super) fn toggle_pin(&mut self) {
+        self.pinned = match self.pinned {
+            Some(_) => None,
+            None => Some(self.index),
+        };
+    }
+}
+
+fn classes(g: &GameDefinition) -> Vec<(Id<Class>, &Class)> {
+    g.classes.iter().collect()
+}
+
+pub(super) fn class_count(g: &GameDefinition) -> usize {
+    classes(g).len()
+}
+
+// What a class is, as ("label", "value") rows: the name heads the text, these come below it.
+// A class has no stats nor abilities yet, they get their rows here when the game defines them.
+fn rows(class: &Class) -> Vec<(String, String)> {
+    vec![("Description".to_owned(), class.description.clone())]
+}
+
+fn push_rows(text: &mut Vec<Text<'static>>, rows: Vec<(String, String)>, theme: &Theme) {
+    let width = rows
+        .iter()
+        .map(|(k, _)| k.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (key, value) in rows {
+        text.push(Text::styled(
+            format!("  {:width$}  ", key, width = width),
+            theme.hint,
+        ));
+        text.push(Text::styled(format!("{}\n", value), theme.text));
+    }
+}
+
+fn class_text(class: &Class, theme: &Theme) -> Vec<Text<'static>> {
+    let mut text = vec![Text::styled(format!("{}\n\n", class.name), theme.title)];
+    push_rows(&mut text, rows(class), theme);
+    text
+}
+
+// The rows of both classes side by side, the values which differ stand out
+fn comparison_text(a: &Class, b: &Class, theme: &Theme) -> Vec<Text<'static>> {
+    let (rows_a, rows_b) = (rows(a), rows(b));
+    let mut keys: Vec<&String> = rows_a.iter().map(|(k, _)| k).collect();
+    for (key, _) in &rows_b {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    let value = |rows: &[(String, String)], key: &str| {
+        rows.iter()
+            .find(|(k, _)| k == key)
+            .map_or("-".to_owned(), |(_, v)| v.clone())
+    };
+
+    let key_width = keys.iter().map(|k| k.chars().count()).max().unwrap_or(0);
+    let a_width = keys
+        .iter()
+        .map(|k| value(&rows_a, k).chars().count())
+        .chain(std::iter::once(a.name.chars().count()))
+        .max()
+        .unwrap_or(0);
+
+    let mut text = vec![Text::styled(
+        format!(
+            "  {:kw$}  {:aw$}  {}\n\n",
+            "",
+            a.name,
+            b.name,
+            kw = key_width,
+            aw = a_width
+        ),
+        theme.title,
+    )];
+    for key in keys {
+        let (va, vb) = (value(&rows_a, key), value(&rows_b, key));
+        let style = if va == vb { theme.text } else { theme.warning };
+        text.push(Text::styled(
+            format!("  {:width$}  ", key, width = key_width),
+            theme.hint,
+        ));
+        text.push(Text::styled(
+            format!("{:width$}  {}\n", va, vb, width = a_width),
+            style,
+        ));
+    }
+    text
+}
+
+// Every class of the game definition, one at a time or two side by side
+pub(super) struct EncyclopediaOverlay<'a>(
+    pub(super) &'a Encyclopedia,
+    pub(super) &'a GameDefinition,
+    pub(super) &'a Scroll,
+    pub(super) &'a Theme,
+);
+impl<'a> Widget for EncyclopediaOverlay<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let area = overlay::centered(area, 80, 80);
+        Clear.draw(area, buf);
+
+        let (encyclopedia, theme) = (self.0, self.3);
+        let classes = classes(self.1);
+        let text = match classes.get(encyclopedia.index) {
+            None => vec![Text::styled("There is no class in this game.", theme.text)],
+            Some((_, class)) => {
+                let mut text = vec![Text::styled(
+                    format!("{} / {}\n", encyclopedia.index + 1, classes.len()),
+                    theme.hint,
+                )];
+                match encyclopedia.pinned.and_then(|pinned| classes.get(pinned)) {
+                    Some((_, pinned)) => text.extend(comparison_text(pinned, class, theme)),
+                    None => text.extend(class_text(class, theme)),
+                }
+                text
+            }
+        };
+
+        let topic = format!("{} {:?}", encyclopedia.index, encyclopedia.pinned);
+        InfoPanel {
+            title: ENCYCLOPEDIA_BLOCK_TITLE,
+            topic: &topic,
+            text: &text,
+            scroll: self.2,
+            theme,
+        }
+        .draw(area, buf);
+    }
+}
+
+// Our character while playing: who it is, where, and everything its class can do
+pub(super) struct CharacterSheet<'a>(
+    pub(super) &'a GlobalState<'a>,
+    pub(super) &'a GameDefinition,
+    pub(super) &'a Scroll,
+    pub(super) &'a Theme,
+);
+impl<'a> Widget for CharacterSheet<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let area = overlay::centered(area, 70, 80);
+        Clear.draw(area, buf);
+
+        let theme = self.3;
+        let mut s = self.0;
+        while let GlobalState::ConfirmExit(c) = s {
+            s = &**c.prev();
+        }
+        let p = match s {
+            GlobalState::PlayGame(PlayGameState::OurTurn(p))
+            | GlobalState::PlayGame(PlayGameState::NotOurTurn(p)) => p.curr(),
+            _ => return,
+        };
+
+        let mut text = Vec::new();
+        match p.game_state.characters.get(p.id) {
+            None => text.push(Text::styled(
+                "Your character is not in the game.",
+                theme.text,
+            )),
+            Some(character) => {
+                let (x, y) = p.map.id_to_xy(character.position);
+                let team = p
+                    .map
+                    .teams
+                    .get(character.team.raw())
+                    .map_or("?", |(name, _)| name.as_str());
+                text.push(Text::styled(format!("{}\n\n", character.name), theme.title));
+                push_rows(
+                    &mut text,
+                    vec![
+                        ("Team".to_owned(), team.to_owned()),
+                        ("Position".to_owned(), format!("({},{})", x, y)),
+                    ],
+                    theme,
+                );
+
+                if let Some(class) = self.1.classes.get(character.class) {
+                    text.push(Text::raw("\n"));
+                    text.extend(class_text(class, theme));
+                }
+            }
+        }
+
+        InfoPanel {
+            title: SHEET_BLOCK_TITLE,
+            topic: SHEET_BLOCK_TITLE,
+            text: &text,
+            scroll: self.2,
+            theme,
+        }
+        .draw(area, buf);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::tui::glyphs::{GlyphSet, Glyphs, Redraw};
+use crate::tui::overlay;
+use crate::tui::restore_terminal;
+use crate::tui::tui::take_over_terminal;
+use crossterm::event::{read, Event};
+use crossterm::{execute, terminal::EnterAlternateScreen};
+use std::io::stdout;
+use tui::backend::CrosstermBackend;
+use tui::layout::Alignment;
+use tui::widgets::{Block, Borders, Paragraph, Text, Widget};
+use tui::Terminal;
+
+// Shows an error which prevents the client from going further, until a key is pressed.
+// Used before the game itself can be shown (e.g. when the server is incompatible).
+pub fn fatal_screen(title: &str, message: &str) {
+    let mut stdout = stdout();
+    take_over_terminal();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout)).unwrap();
+    terminal.hide_cursor().unwrap();
+
+    // the theme is not loaded yet
+    let glyphs = Glyphs::from_set(GlyphSet::detect());
+    let text = [
+        Text::raw(format!("\n{}\n\n", message)),
+        Text::raw("Press any key to quit"),
+    ];
+    terminal
+        .draw(|mut f| {
+            let size = f.size();
+            let area = overlay::centered(size, 70, 40);
+            Paragraph::new(text.iter())
+                .block(Block::default().title(title).borders(Borders::ALL))
+                .alignment(Alignment::Center)
+                .wrap(true)
+                .render(&mut f, area);
+            Redraw(&glyphs).render(&mut f, size);
+        })
+        .unwrap();
+
+    while !matches!(read().unwrap(), Event::Key(_)) {}
+
+    restore_terminal();
+}
@@ -0,0 +1,133 @@
+use ::tui::buffer::Buffer;
+use ::tui::layout::Rect;
+use ::tui::widgets::Widget;
+use std::env;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphSet {
+    Double,
+    Single,
+    Rounded,
+    // for fonts and terminals without box-drawing characters
+    Ascii,
+}
+
+impl GlyphSet {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "double" => Some(GlyphSet::Double),
+            "single" => Some(GlyphSet::Single),
+            "rounded" => Some(GlyphSet::Rounded),
+            "ascii" => Some(GlyphSet::Ascii),
+            _ => None,
+        }
+    }
+
+    // Box-drawing characters need a UTF-8 locale, ASCII is the only safe choice otherwise
+    pub(super) fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        if locale.contains("utf-8") || locale.contains("utf8") {
+            GlyphSet::Double
+        } else {
+            GlyphSet::Ascii
+        }
+    }
+}
+
+// The characters used to draw the lines of the map grid and of the panels.
+// Junctions are named after their position in a grid: `top` is the one between two cells of the
+// first row, `cross` the one between four cells.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Glyphs {
+    pub(super) horizontal: char,
+    pub(super) vertical: char,
+    pub(super) top_left: char,
+    pub(super) top: char,
+    pub(super) top_right: char,
+    pub(super) left: char,
+    pub(super) cross: char,
+    pub(super) right: char,
+    pub(super) bottom_left: char,
+    pub(super) bottom: char,
+    pub(super) bottom_right: char,
+    // the visible part of a scrollbar
+    pub(super) thumb: char,
+    // marks the favorite maps
+    pub(super) favorite: char,
+}
+
+// What tui draws the borders of its blocks with, in the same order as the fields of Glyphs
+const TUI_LINES: [char; 11] = ['─', '│', '┌', '┬', '┐', '├', '┼', '┤', '└', '┴', '┘'];
+
+impl Glyphs {
+    pub(super) fn from_set(set: GlyphSet) -> Self {
+        let chars: [char; 11] = match set {
+            GlyphSet::Double => ['═', '║', '╔', '╦', '╗', '╠', '╬', '╣', '╚', '╩', '╝'],
+            GlyphSet::Single => TUI_LINES,
+            GlyphSet::Rounded => ['─', '│', '╭', '┬', '╮', '├', '┼', '┤', '╰', '┴', '╯'],
+            GlyphSet::Ascii => ['-', '|', '+', '+', '+', '+', '+', '+', '+', '+', '+'],
+        };
+        Glyphs {
+            horizontal: chars[0],
+            vertical: chars[1],
+            top_left: chars[2],
+            top: chars[3],
+            top_right: chars[4],
+            left: chars[5],
+            cross: chars[6],
+            right: chars[7],
+            bottom_left: chars[8],
+            bottom: chars[9],
+            bottom_right: chars[10],
+            thumb: if set == GlyphSet::Ascii { '#' } else { '█' },
+            favorite: if set == GlyphSet::Ascii { '*' } else { '★' },
+        }
+    }
+
+    fn chars(&self) -> [char; 11] {
+        [
+            self.horizontal,
+            self.vertical,
+            self.top_left,
+            self.top,
+            self.top_right,
+            self.left,
+            self.cross,
+            self.right,
+            self.bottom_left,
+            self.bottom,
+            self.bottom_right,
+        ]
+    }
+}
+
+// tui 0.8 has no choice of border characters: once everything is drawn, this replaces the ones
+// of its blocks by ours
+pub(super) struct Redraw<'a>(pub(super) &'a Glyphs);
+impl<'a> Widget for Redraw<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let glyphs = self.0.chars();
+        if glyphs == TUI_LINES {
+            return;
+        }
+
+        for x in area.left()..area.right() {
+            for y in area.top()..area.bottom() {
+                let cell = buf.get_mut(x, y);
+                let mut symbol = cell.symbol.chars();
+                let line = match (symbol.next(), symbol.next()) {
+                    (Some(c), None) => TUI_LINES.iter().position(|l| *l == c),
+                    _ => None,
+                };
+                if let Some(index) = line {
+                    cell.set_char(glyphs[index]);
+                }
+            }
+        }
+    }
+}
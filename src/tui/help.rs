@@ -0,0 +1,194 @@
+use crate::tui::keymap::{Action, Context, Keymap};
+use crate::tui::overlay::{self, Clear};
+use crate::tui::theme::Theme;
+use ::tui::buffer::Buffer;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::widgets::{Block, Borders, Paragraph, Text, Widget};
+use pewcraft_frontend::state::{
+    CreateCharacterState, CreateOrJoinState, GlobalState, PlayGameState, State,
+};
+
+const HELP_BLOCK_TITLE: &str = "Help (press F1 or esc to close)";
+
+// Actions that do something in the given state, most important first
+fn actions(s: &GlobalState) -> Vec<(Action, &'static str)> {
+    let mut actions = match s {
+        GlobalState::CreateOrJoin(CreateOrJoinState::Create(_)) => vec![
+            (Action::Confirm, "create a new game"),
+            (Action::Right, "switch to JOIN"),
+        ],
+        GlobalState::CreateOrJoin(CreateOrJoinState::Join(_)) => vec![
+            (Action::Confirm, "join the game with the login"),
+            (Action::Paste, "paste a login"),
+            (Action::Cancel, "switch to CREATE"),
+        ],
+        GlobalState::SelectMap(m) if m.curr().filtering => vec![
+            (Action::Confirm, "keep the filter"),
+            (Action::Backspace, "erase the last character of the filter"),
+            (Action::Cancel, "clear the filter"),
+            (Action::Up, "previous map"),
+            (Action::Down, "next map"),
+            (Action::Sort, "sort by size or teams"),
+            (Action::Favorite, "mark or unmark as favorite"),
+        ],
+        GlobalState::SelectMap(_) => vec![
+            (Action::Confirm, "create a game on this map"),
+            (Action::Up, "previous map"),
+            (Action::Down, "next map"),
+            (Action::Filter, "filter the maps by name"),
+            (Action::Sort, "sort by size or teams"),
+            (Action::Favorite, "mark or unmark as favorite"),
+            (Action::Cancel, "back to CREATE or JOIN"),
+        ],
+        GlobalState::CreateCharacter(CreateCharacterState::Team(_)) => vec![
+            (Action::Confirm, "join this team"),
+            (Action::Left, "previous team"),
+            (Action::Right, "next team"),
+            (Action::Copy, "copy the game id"),
+        ],
+        GlobalState::CreateCharacter(CreateCharacterState::Class(_)) => vec![
+            (Action::Confirm, "play this class"),
+            (Action::Left, "previous class"),
+            (Action::Right, "next class"),
+            (Action::Copy, "copy the game id"),
+        ],
+        GlobalState::CreateCharacter(CreateCharacterState::Position(_)) => vec![
+            (Action::Confirm, "start from this position"),
+            (Action::Left, "previous position"),
+            (Action::Right, "next position"),
+            (Action::Copy, "copy the game id"),
+        ],
+        GlobalState::CreateCharacter(CreateCharacterState::Name(_)) => vec![
+            (Action::Confirm, "create the character"),
+            (Action::Backspace, "erase the last character"),
+            (Action::Paste, "paste a name"),
+        ],
+        GlobalState::WaitForGameCreation(_) => vec![
+            (Action::AddBot, "fill a slot with a bot"),
+            (Action::Copy, "copy the character login"),
+        ],
+        GlobalState::PlayGame(PlayGameState::OurTurn(_)) => vec![
+            (
+                Action::Confirm,
+                "move, attack or end the turn on the cursor",
+            ),
+            (Action::Left, "move the cursor left"),
+            (Action::Right, "move the cursor right"),
+            (Action::Up, "move the cursor up"),
+            (Action::Down, "move the cursor down"),
+            (Action::Cancel, "bring the cursor back to your character"),
+            (Action::CharacterSheet, "show your character sheet"),
+            (Action::Copy, "copy the character login"),
+        ],
+        GlobalState::PlayGame(PlayGameState::NotOurTurn(_)) => vec![
+            (Action::CharacterSheet, "show your character sheet"),
+            (Action::Copy, "copy the character login"),
+        ],
+        GlobalState::GameOver(_) => vec![(Action::Confirm, "go back to the start")],
+        GlobalState::ConfirmExit(_) => vec![
+            (Action::Confirm, "validate the choice"),
+            (Action::Left, "switch between quitting and staying"),
+            (Action::Cancel, "stay"),
+        ],
+        GlobalState::Exit => vec![],
+    };
+
+    actions.push((Action::Exit, "quit"));
+    actions.push((Action::Classes, "browse and compare the classes"));
+    actions.push((Action::Help, "show or hide this help"));
+    actions.push((Action::ToggleLog, "show or hide the log console"));
+    actions.push((Action::LogLevel, "change the log level"));
+    actions.push((Action::LogModule, "only show the logs of one module"));
+    actions.push((Action::Debug, "show or hide the debug overlay"));
+    actions.push((Action::Layout, "switch between layouts"));
+    actions.push((Action::TogglePanel, "hide or show the info panel"));
+    actions.push((Action::GrowPanel, "enlarge the info panel"));
+    actions.push((Action::ShrinkPanel, "shrink the info panel"));
+    actions.push((Action::PageDown, "scroll the info panel down"));
+    actions.push((Action::PageUp, "scroll the info panel up"));
+    actions
+}
+
+// Keys bound to the actions of the current state, with their description
+fn bindings(s: &GlobalState, keymap: &Keymap) -> Vec<(String, &'static str)> {
+    let context = Context::from(&s.expected_event());
+    actions(s)
+        .into_iter()
+        .filter_map(|(action, description)| {
+            let keys: Vec<String> = keymap
+                .keys(context, action)
+                .map(|k| k.to_string())
+                .collect();
+            if keys.is_empty() {
+                None
+            } else {
+                Some((keys.join(" / "), description))
+            }
+        })
+        .collect()
+}
+
+// One line summary of the most important keys, shown at the bottom of every screen
+pub(super) struct HintBar<'a>(
+    pub(super) &'a GlobalState<'a>,
+    pub(super) &'a Keymap,
+    pub(super) &'a Theme,
+);
+impl<'a> Widget for HintBar<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let context = Context::from(&self.0.expected_event());
+        let mut hint = String::new();
+        for (action, description) in actions(self.0) {
+            let key = match self.1.keys(context, action).next() {
+                Some(key) => key.to_string(),
+                None => continue,
+            };
+            let entry = format!("{}: {}", key, description);
+            if hint.len() + entry.len() + 3 > area.width as usize {
+                break;
+            }
+            if !hint.is_empty() {
+                hint.push_str(" | ");
+            }
+            hint.push_str(&entry);
+        }
+
+        buf.set_string(area.x, area.y, &hint, self.2.hint);
+    }
+}
+
+pub(super) struct HelpOverlay<'a>(
+    pub(super) &'a GlobalState<'a>,
+    pub(super) &'a Keymap,
+    pub(super) &'a Theme,
+);
+impl<'a> Widget for HelpOverlay<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let area = overlay::centered(area, 60, 60);
+        Clear.draw(area, buf);
+
+        let theme = self.2;
+        let bindings = bindings(self.0, self.1);
+        let key_width = bindings.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+        let text: Vec<Text> = bindings
+            .iter()
+            .map(|(keys, description)| {
+                Text::styled(
+                    format!("  {:width$}   {}\n", keys, description, width = key_width),
+                    theme.text,
+                )
+            })
+            .collect();
+
+        Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title(HELP_BLOCK_TITLE)
+                    .title_style(theme.title)
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            )
+            .alignment(Alignment::Left)
+            .draw(area, buf);
+    }
+}
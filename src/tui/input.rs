@@ -1,24 +1,45 @@
-use crate::state::{Event, ExpectedEvent};
-use crossterm::event::{read, KeyCode};
+use crate::tui::keymap::{Action, Context, Keymap};
+use crossterm::event::{poll, read, KeyCode, KeyEvent, MouseButton, MouseEvent};
 use futures::executor::block_on;
 use futures::future::FutureExt;
 use futures::pin_mut;
 use futures::select;
+use pewcraft_frontend::state::{Event, ExpectedEvent};
+use std::time::Duration;
 
-type RawEvent = crossterm::event::Event;
+pub type RawEvent = crossterm::event::Event;
 
 pub enum TuiEvent {
     StateEvent(Event),
     CopyClipboard,
     PasteClipboard,
+    ToggleHelp,
+    ToggleLog,
+    CycleLogLevel,
+    CycleLogModule,
+    ToggleDebug,
+    CycleLayout,
+    TogglePanel,
+    GrowPanel,
+    ShrinkPanel,
+    PageUp,
+    PageDown,
+    ToggleClasses,
+    ToggleSheet,
+    // screen column and row of a left click
+    Click(u16, u16),
 }
 
-pub fn get(ev: ExpectedEvent) -> TuiEvent {
-    block_on(get_async(ev))
+// `pending` holds an event that was read ahead, it is handled before reading anything new
+pub fn get(ev: ExpectedEvent, keymap: &Keymap, pending: &mut Option<RawEvent>) -> TuiEvent {
+    if let Some(event) = pending.take() {
+        return translate(event, Context::from(&ev), keymap, pending);
+    }
+    block_on(get_async(ev, keymap, pending))
 }
 
-async fn get_async(ev: ExpectedEvent) -> TuiEvent {
-    let input = user_input(ev).fuse();
+async fn get_async(ev: ExpectedEvent, keymap: &Keymap, pending: &mut Option<RawEvent>) -> TuiEvent {
+    let input = user_input(ev, keymap, pending).fuse();
     let timeout = timeout().fuse();
 
     pin_mut!(input, timeout);
@@ -29,36 +50,105 @@ async fn get_async(ev: ExpectedEvent) -> TuiEvent {
     }
 }
 
-fn char_to_event(c: char, ev: ExpectedEvent) -> TuiEvent {
-    TuiEvent::StateEvent(if matches!(ev, ExpectedEvent::Char) {
-        Event::PrintableString(c.to_string())
-    } else {
-        match c {
-            'q' => Event::Exit,
-            'h' => Event::Left,
-            'l' => Event::Right,
-            'j' => Event::Down,
-            'k' => Event::Up,
-            'y' => return TuiEvent::CopyClipboard,
-            c => Event::PrintableString(c.to_string()),
-        }
+fn action_to_event(action: Action) -> TuiEvent {
+    TuiEvent::StateEvent(match action {
+        Action::Exit => Event::Exit,
+        Action::Left => Event::Left,
+        Action::Right => Event::Right,
+        Action::Up => Event::Up,
+        Action::Down => Event::Down,
+        Action::Confirm => Event::Confirm,
+        Action::Cancel => Event::Cancel,
+        Action::Backspace => Event::Backspace,
+        Action::AddBot => Event::AddBot,
+        Action::Sort => Event::Sort,
+        Action::Favorite => Event::Favorite,
+        Action::Filter => Event::Filter,
+        Action::Copy => return TuiEvent::CopyClipboard,
+        Action::Paste => return TuiEvent::PasteClipboard,
+        Action::Help => return TuiEvent::ToggleHelp,
+        Action::ToggleLog => return TuiEvent::ToggleLog,
+        Action::LogLevel => return TuiEvent::CycleLogLevel,
+        Action::LogModule => return TuiEvent::CycleLogModule,
+        Action::Debug => return TuiEvent::ToggleDebug,
+        Action::Layout => return TuiEvent::CycleLayout,
+        Action::TogglePanel => return TuiEvent::TogglePanel,
+        Action::GrowPanel => return TuiEvent::GrowPanel,
+        Action::ShrinkPanel => return TuiEvent::ShrinkPanel,
+        Action::PageUp => return TuiEvent::PageUp,
+        Action::PageDown => return TuiEvent::PageDown,
+        Action::Classes => return TuiEvent::ToggleClasses,
+        Action::CharacterSheet => return TuiEvent::ToggleSheet,
     })
 }
 
-async fn user_input(ev: ExpectedEvent) -> TuiEvent {
-    TuiEvent::StateEvent(match read().unwrap() {
-        RawEvent::Key(key) => match key.code {
-            KeyCode::Left => Event::Left,
-            KeyCode::Right => Event::Right,
-            KeyCode::Up => Event::Up,
-            KeyCode::Down => Event::Down,
-            KeyCode::Char(c) => return char_to_event(c, ev),
-            KeyCode::Enter => Event::Confirm,
-            KeyCode::Backspace => Event::Backspace,
-            _ => Event::Other,
+// crossterm 0.16 does not support bracketed paste, this is what we do instead.
+// Pasting in a terminal sends the whole string as a burst of key presses, so we group whatever
+// is already pending into one string instead of feeding the text fields one character per frame.
+// The first event that is not a plain character ends the burst and is kept for the next frame,
+// except for the newline a copied line usually ends with: pasting must not submit the field.
+fn read_burst(
+    mut string: String,
+    context: Context,
+    keymap: &Keymap,
+    pending: &mut Option<RawEvent>,
+) -> String {
+    while poll(Duration::from_millis(0)).unwrap() {
+        match read().unwrap() {
+            RawEvent::Key(
+                key @ KeyEvent {
+                    code: KeyCode::Char(c),
+                    ..
+                },
+            ) if keymap.action(context, key).is_none() => string.push(c),
+            RawEvent::Key(KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            }) if !poll(Duration::from_millis(0)).unwrap() => break,
+            event => {
+                *pending = Some(event);
+                break;
+            }
+        }
+    }
+    string
+}
+
+async fn user_input(
+    ev: ExpectedEvent,
+    keymap: &Keymap,
+    pending: &mut Option<RawEvent>,
+) -> TuiEvent {
+    translate(read().unwrap(), Context::from(&ev), keymap, pending)
+}
+
+fn translate(
+    event: RawEvent,
+    context: Context,
+    keymap: &Keymap,
+    pending: &mut Option<RawEvent>,
+) -> TuiEvent {
+    match event {
+        RawEvent::Key(key) => match (keymap.action(context, key), key.code) {
+            (Some(action), _) => action_to_event(action),
+            (None, KeyCode::Char(c)) if context == Context::Text => {
+                let string = read_burst(c.to_string(), context, keymap, pending);
+                TuiEvent::StateEvent(Event::PrintableString(string))
+            }
+            (None, _) => TuiEvent::StateEvent(Event::Other),
         },
-        _ => Event::Other,
-    })
+        RawEvent::Mouse(MouseEvent::Down(MouseButton::Left, column, row, _)) => {
+            TuiEvent::Click(column, row)
+        }
+        // scrolling cycles through whatever is being selected
+        RawEvent::Mouse(MouseEvent::ScrollUp(..)) if context != Context::Text => {
+            TuiEvent::StateEvent(Event::Left)
+        }
+        RawEvent::Mouse(MouseEvent::ScrollDown(..)) if context != Context::Text => {
+            TuiEvent::StateEvent(Event::Right)
+        }
+        _ => TuiEvent::StateEvent(Event::Other),
+    }
 }
 
 async fn timeout() -> () {
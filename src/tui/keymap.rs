@@ -0,0 +1,634 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use pewcraft_frontend::config;
+use pewcraft_frontend::state::ExpectedEvent;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const KEYMAP_FILE: &str = "keymap.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Exit,
+    Left,
+    Right,
+    Up,
+    Down,
+    Confirm,
+    Cancel,
+    Backspace,
+    Copy,
+    Paste,
+    Help,
+    ToggleLog,
+    LogLevel,
+    LogModule,
+    Debug,
+    AddBot,
+    Layout,
+    TogglePanel,
+    GrowPanel,
+    ShrinkPanel,
+    PageUp,
+    PageDown,
+    Classes,
+    CharacterSheet,
+    Sort,
+    Favorite,
+    Filter,
+}
+
+impl Action {
+    const ALL: [Action; 27] = [
+        Action::Exit,
+        Action::Left,
+        Action::Right,
+        Action::Up,
+        Action::Down,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Backspace,
+        Action::Copy,
+        Action::Paste,
+        Action::Help,
+        Action::ToggleLog,
+        Action::LogLevel,
+        Action::LogModule,
+        Action::Debug,
+        Action::AddBot,
+        Action::Layout,
+        Action::TogglePanel,
+        Action::GrowPanel,
+        Action::ShrinkPanel,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Classes,
+        Action::CharacterSheet,
+        Action::Sort,
+        Action::Favorite,
+        Action::Filter,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Exit => "exit",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Backspace => "backspace",
+            Action::Copy => "copy",
+            Action::Paste => "paste",
+            Action::Help => "help",
+            Action::ToggleLog => "toggle_log",
+            Action::LogLevel => "log_level",
+            Action::LogModule => "log_module",
+            Action::Debug => "debug",
+            Action::AddBot => "add_bot",
+            Action::Layout => "layout",
+            Action::TogglePanel => "toggle_panel",
+            Action::GrowPanel => "grow_panel",
+            Action::ShrinkPanel => "shrink_panel",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Classes => "classes",
+            Action::CharacterSheet => "character_sheet",
+            Action::Sort => "sort",
+            Action::Favorite => "favorite",
+            Action::Filter => "filter",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Menu,
+    Text,
+    Map,
+    Targeting,
+}
+
+impl Context {
+    const ALL: [Context; 4] = [
+        Context::Menu,
+        Context::Text,
+        Context::Map,
+        Context::Targeting,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Context::Menu => "menu",
+            Context::Text => "text",
+            Context::Map => "map",
+            Context::Targeting => "targeting",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Context::ALL.iter().copied().find(|c| c.name() == name)
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl From<&ExpectedEvent> for Context {
+    fn from(ev: &ExpectedEvent) -> Self {
+        match ev {
+            ExpectedEvent::Char => Context::Text,
+            ExpectedEvent::Selection => Context::Map,
+            ExpectedEvent::Target => Context::Targeting,
+            ExpectedEvent::SelectionVertical
+            | ExpectedEvent::SelectionHorizontal
+            | ExpectedEvent::None => Context::Menu,
+        }
+    }
+}
+
+// A key press, optionally with modifiers (a "chord" such as ctrl-v)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::empty();
+        let mut parts: Vec<&str> = s.split('-').collect();
+        // "-" and "ctrl--" are valid keys, the split gives us an empty last part
+        if s.ends_with('-') {
+            parts.pop();
+            parts.pop();
+            parts.push("-");
+        }
+        let (key, mods) = parts.split_last().ok_or_else(|| "empty key".to_owned())?;
+
+        for m in mods {
+            modifiers |= match m.to_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                other => return Err(format!("unknown modifier '{}' in '{}'", other, s)),
+            }
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "tab" => KeyCode::Tab,
+            "delete" | "del" => KeyCode::Delete,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            lower if lower.len() > 1 && lower.starts_with('f') => match lower[1..].parse() {
+                Ok(n) if n >= 1 && n <= 12 => KeyCode::F(n),
+                _ => return Err(format!("unknown key '{}'", key)),
+            },
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key '{}'", key)),
+                }
+            }
+        };
+
+        Ok(Key { code, modifiers })
+    }
+
+    fn from_event(event: KeyEvent) -> Self {
+        // the shift modifier is already part of the character itself
+        let modifiers = match event.code {
+            KeyCode::Char(_) => event.modifiers - KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+        Key {
+            code: event.code,
+            modifiers,
+        }
+    }
+
+    fn is_printable(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Vim,
+    Arrows,
+    Wasd,
+}
+
+impl Preset {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vim" => Some(Preset::Vim),
+            "arrows" => Some(Preset::Arrows),
+            "wasd" => Some(Preset::Wasd),
+            _ => None,
+        }
+    }
+
+    fn directions(self) -> &'static [(&'static str, Action)] {
+        match self {
+            Preset::Arrows => &[],
+            Preset::Vim => &[
+                ("h", Action::Left),
+                ("l", Action::Right),
+                ("k", Action::Up),
+                ("j", Action::Down),
+            ],
+            Preset::Wasd => &[
+                ("a", Action::Left),
+                ("d", Action::Right),
+                ("w", Action::Up),
+                ("s", Action::Down),
+            ],
+        }
+    }
+
+    // Bindings of the given context, before the user's overrides
+    fn bindings(self, context: Context) -> Vec<(Key, Action)> {
+        let mut bindings = vec![
+            ("enter", Action::Confirm),
+            ("esc", Action::Cancel),
+            ("backspace", Action::Backspace),
+            ("ctrl-v", Action::Paste),
+            ("ctrl-c", Action::Exit),
+            ("left", Action::Left),
+            ("right", Action::Right),
+            ("up", Action::Up),
+            ("down", Action::Down),
+            ("f1", Action::Help),
+            ("f2", Action::ToggleLog),
+            ("f3", Action::LogLevel),
+            ("f4", Action::LogModule),
+            ("f5", Action::Layout),
+            ("f6", Action::TogglePanel),
+            ("pageup", Action::PageUp),
+            ("pagedown", Action::PageDown),
+            ("f7", Action::Classes),
+            ("f8", Action::CharacterSheet),
+            // keys that do not type anything: the map browser filters what is typed
+            ("tab", Action::Sort),
+            ("ctrl-f", Action::Favorite),
+            ("f12", Action::Debug),
+        ];
+
+        if context != Context::Text {
+            bindings.extend_from_slice(&[
+                ("q", Action::Exit),
+                ("y", Action::Copy),
+                ("?", Action::Help),
+                ("b", Action::AddBot),
+                ("+", Action::GrowPanel),
+                ("-", Action::ShrinkPanel),
+                ("c", Action::Classes),
+                ("i", Action::CharacterSheet),
+                ("/", Action::Filter),
+            ]);
+            bindings.extend_from_slice(self.directions());
+        }
+
+        bindings
+            .into_iter()
+            .map(|(key, action)| (Key::parse(key).unwrap(), action))
+            .collect()
+    }
+}
+
+// Content of the keymap file, e.g.:
+// {
+//   "preset": "wasd",
+//   "bindings": { "map": { "exit": ["ctrl-q"], "confirm": ["enter", "space"] } }
+// }
+// The keys listed for an action replace the preset's keys for that action in that context.
+#[derive(Debug, Default, Deserialize)]
+struct KeymapFile {
+    preset: Option<String>,
+    #[serde(default)]
+    bindings: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: [Vec<(Key, Action)>; 4],
+}
+
+impl Keymap {
+    pub fn from_preset(preset: Preset) -> Self {
+        Keymap {
+            bindings: [
+                preset.bindings(Context::Menu),
+                preset.bindings(Context::Text),
+                preset.bindings(Context::Map),
+                preset.bindings(Context::Targeting),
+            ],
+        }
+    }
+
+    // Loads the keymap from the config directory.
+    // Problems (unknown keys, conflicting bindings...) do not prevent starting, they are returned
+    // so that they can be reported to the user.
+    pub fn load() -> (Self, Vec<String>) {
+        match config::read::<KeymapFile>(KEYMAP_FILE) {
+            Ok(Some(file)) => Keymap::from_file(file),
+            Ok(None) => (Keymap::from_preset(Preset::Vim), Vec::new()),
+            Err(e) => (Keymap::from_preset(Preset::Vim), vec![e]),
+        }
+    }
+
+    fn from_file(file: KeymapFile) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let preset = match file.preset.as_deref().map(|p| (p, Preset::from_name(p))) {
+            None => Preset::Vim,
+            Some((_, Some(preset))) => preset,
+            Some((name, None)) => {
+                problems.push(format!("unknown preset '{}', using 'vim'", name));
+                Preset::Vim
+            }
+        };
+        let mut keymap = Keymap::from_preset(preset);
+
+        for (context_name, actions) in file.bindings {
+            let context = match Context::from_name(&context_name) {
+                Some(context) => context,
+                None => {
+                    problems.push(format!("unknown context '{}'", context_name));
+                    continue;
+                }
+            };
+
+            let mut user_bindings: Vec<(Key, Action)> = Vec::new();
+            for (action_name, keys) in actions {
+                let action = match Action::from_name(&action_name) {
+                    Some(action) => action,
+                    None => {
+                        problems.push(format!("unknown action '{}'", action_name));
+                        continue;
+                    }
+                };
+
+                for key in keys {
+                    let key = match Key::parse(&key) {
+                        Ok(key) => key,
+                        Err(e) => {
+                            problems.push(format!("[{}] {}", context_name, e));
+                            continue;
+                        }
+                    };
+                    match user_bindings.iter().find(|(k, _)| *k == key) {
+                        Some((_, other)) if *other != action => problems.push(format!(
+                            "[{}] '{}' is bound to both '{}' and '{}', keeping '{}'",
+                            context_name,
+                            key,
+                            other.name(),
+                            action.name(),
+                            other.name()
+                        )),
+                        Some(_) => {}
+                        None => user_bindings.push((key, action)),
+                    }
+                }
+            }
+
+            // the user's bindings replace the preset ones for the same action or the same key
+            let bindings = &mut keymap.bindings[context.index()];
+            let taken = |key: &Key| user_bindings.iter().find(|(k, _)| k == key);
+            for (key, action) in bindings.iter() {
+                let other = match taken(key) {
+                    Some((_, other)) if other != action => other,
+                    _ => continue,
+                };
+                // the action was moved on purpose
+                if user_bindings.iter().any(|(_, a)| a == action) {
+                    continue;
+                }
+                let key_left = bindings
+                    .iter()
+                    .any(|(k, a)| a == action && taken(k).is_none());
+                problems.push(format!(
+                    "[{}] '{}' is now bound to '{}' instead of '{}'{}",
+                    context_name,
+                    key,
+                    other.name(),
+                    action.name(),
+                    if key_left {
+                        ""
+                    } else {
+                        ", which has no key left"
+                    }
+                ));
+            }
+            bindings.retain(|(key, action)| {
+                !user_bindings.iter().any(|(k, a)| k == key || a == action)
+            });
+            bindings.extend(user_bindings);
+        }
+
+        (keymap, problems)
+    }
+
+    pub fn action(&self, context: Context, event: KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        let action = self.bindings[context.index()]
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, action)| *action);
+
+        // plain characters are never actions when typing text, whatever the user's config says
+        if context == Context::Text && key.is_printable() {
+            None
+        } else {
+            action
+        }
+    }
+
+    pub fn keys(&self, context: Context, action: Action) -> impl Iterator<Item = &Key> {
+        self.bindings[context.index()]
+            .iter()
+            .filter(move |(_, a)| *a == action)
+            .map(|(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        Key { code, modifiers }
+    }
+
+    fn from_json(json: &str) -> (Keymap, Vec<String>) {
+        Keymap::from_file(serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn parses_keys_and_chords() {
+        let none = KeyModifiers::empty();
+        assert_eq!(Key::parse("q"), Ok(key(KeyCode::Char('q'), none)));
+        assert_eq!(Key::parse("Q"), Ok(key(KeyCode::Char('Q'), none)));
+        assert_eq!(Key::parse("Enter"), Ok(key(KeyCode::Enter, none)));
+        assert_eq!(Key::parse("space"), Ok(key(KeyCode::Char(' '), none)));
+        assert_eq!(Key::parse("f12"), Ok(key(KeyCode::F(12), none)));
+        assert_eq!(Key::parse("f"), Ok(key(KeyCode::Char('f'), none)));
+        assert_eq!(
+            Key::parse("ctrl-alt-v"),
+            Ok(key(
+                KeyCode::Char('v'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_the_dash_key() {
+        let none = KeyModifiers::empty();
+        assert_eq!(Key::parse("-"), Ok(key(KeyCode::Char('-'), none)));
+        assert_eq!(
+            Key::parse("ctrl--"),
+            Ok(key(KeyCode::Char('-'), KeyModifiers::CONTROL))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Key::parse("").is_err());
+        assert!(Key::parse("f13").is_err());
+        assert!(Key::parse("nope").is_err());
+        assert!(Key::parse("hyper-q").is_err());
+    }
+
+    #[test]
+    fn displays_keys_as_they_are_parsed() {
+        for s in &["q", "ctrl-v", "alt-left", "f1", "space", "-", "pagedown"] {
+            assert_eq!(Key::parse(s).unwrap().to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn user_bindings_replace_the_preset_ones() {
+        let (keymap, problems) =
+            from_json(r#"{ "preset": "wasd", "bindings": { "map": { "exit": ["ctrl-q"] } } }"#);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let exit: Vec<_> = keymap.keys(Context::Map, Action::Exit).collect();
+        assert_eq!(exit, vec![&Key::parse("ctrl-q").unwrap()]);
+        // other contexts keep the preset
+        assert!(keymap
+            .keys(Context::Menu, Action::Exit)
+            .any(|k| *k == Key::parse("q").unwrap()));
+        assert!(keymap
+            .keys(Context::Map, Action::Up)
+            .any(|k| *k == Key::parse("w").unwrap()));
+    }
+
+    #[test]
+    fn reports_unknown_names_and_keeps_going() {
+        let (keymap, problems) = from_json(
+            r#"{
+                "preset": "emacs",
+                "bindings": {
+                    "everywhere": { "exit": ["q"] },
+                    "menu": { "jump": ["j"], "help": ["f13", "h"] }
+                }
+            }"#,
+        );
+        // unknown preset, context, key and action, and 'h' taken from 'left'
+        assert_eq!(problems.len(), 5, "{:?}", problems);
+        // the valid binding is still applied, on top of the default preset
+        assert!(keymap
+            .keys(Context::Menu, Action::Help)
+            .any(|k| *k == Key::parse("h").unwrap()));
+        assert!(keymap
+            .keys(Context::Map, Action::Left)
+            .any(|k| *k == Key::parse("h").unwrap()));
+    }
+
+    #[test]
+    fn reports_keys_bound_twice_by_the_user() {
+        let (keymap, problems) =
+            from_json(r#"{ "bindings": { "map": { "copy": ["x"], "exit": ["x"] } } }"#);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(keymap
+            .keys(Context::Map, Action::Copy)
+            .any(|k| *k == Key::parse("x").unwrap()));
+        let x = Key::parse("x").unwrap();
+        assert!(keymap.keys(Context::Map, Action::Exit).all(|k| *k != x));
+    }
+
+    #[test]
+    fn reports_keys_taken_from_the_preset() {
+        // help keeps f1 and exit keeps 'q'
+        let (_, problems) =
+            from_json(r#"{ "bindings": { "map": { "copy": ["?"], "debug": ["ctrl-c"] } } }"#);
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems.iter().all(|p| !p.contains("no key left")));
+
+        let (keymap, problems) =
+            from_json(r#"{ "bindings": { "map": { "copy": ["ctrl-c", "q"] } } }"#);
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems.iter().all(|p| p.contains("no key left")));
+        assert_eq!(keymap.keys(Context::Map, Action::Exit).count(), 0);
+    }
+
+    #[test]
+    fn moving_an_action_to_another_key_is_not_a_conflict() {
+        let (_, problems) =
+            from_json(r#"{ "bindings": { "map": { "copy": ["q"], "exit": ["ctrl-q"] } } }"#);
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::logger;
+use crate::tui::overlay::Clear;
+use crate::tui::theme::Theme;
+use ::tui::buffer::Buffer;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::widgets::{Block, Borders, Paragraph, Text, Widget};
+use log::Level;
+
+const LEVELS: [Level; 5] = [
+    Level::Error,
+    Level::Warn,
+    Level::Info,
+    Level::Debug,
+    Level::Trace,
+];
+
+// Recent log records, drawn over the bottom of the screen when visible
+pub(super) struct LogConsole {
+    pub(super) visible: bool,
+    level: Level,
+    // only show records of this module (and its submodules)
+    module: Option<String>,
+}
+
+impl LogConsole {
+    pub(super) fn new() -> Self {
+        LogConsole {
+            visible: false,
+            level: Level::Info,
+            module: None,
+        }
+    }
+
+    pub(super) fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Error -> Warn -> ... -> Trace -> Error
+    pub(super) fn cycle_level(&mut self) {
+        let index = LEVELS.iter().position(|l| *l == self.level).unwrap_or(0);
+        self.level = LEVELS[(index + 1) % LEVELS.len()];
+    }
+
+    // All modules -> each module seen in the logs -> all modules
+    pub(super) fn cycle_module(&mut self) {
+        let mut modules: Vec<String> = logger::records().into_iter().map(|r| r.target).collect();
+        modules.sort();
+        modules.dedup();
+
+        self.module = match &self.module {
+            None => modules.into_iter().next(),
+            Some(current) => modules.into_iter().find(|m| m > current),
+        };
+    }
+}
+
+// Draws the console with the colors of the theme
+pub(super) struct LogView<'a>(pub(super) &'a LogConsole, pub(super) &'a Theme);
+
+impl<'a> Widget for LogView<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let console = self.0;
+        let theme = self.1;
+        let height = area.height / 2;
+        let area = Rect::new(area.x, area.bottom() - height, area.width, height);
+        Clear.draw(area, buf);
+
+        let level = console.level;
+        let module = &console.module;
+        let records: Vec<_> = logger::records()
+            .into_iter()
+            .filter(|r| r.level <= level)
+            .filter(|r| match module {
+                Some(module) => r.target.starts_with(module.as_str()),
+                None => true,
+            })
+            .collect();
+
+        // the most recent records fill the bottom of the console
+        let lines = area.height.saturating_sub(2) as usize;
+        let text: Vec<Text> = records[records.len().saturating_sub(lines)..]
+            .iter()
+            .map(|r| {
+                let style = match r.level {
+                    Level::Error => theme.error,
+                    Level::Warn => theme.warning,
+                    _ => theme.text,
+                };
+                Text::styled(
+                    format!("{:<5} {}: {}\n", r.level, r.target, r.message),
+                    style,
+                )
+            })
+            .collect();
+
+        let title = format!(
+            "Log | level: {} | module: {}",
+            console.level,
+            console.module.as_deref().unwrap_or("all")
+        );
+        Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title(&title)
+                    .title_style(theme.title)
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            )
+            .alignment(Alignment::Left)
+            .draw(area, buf);
+    }
+}
@@ -1,5 +1,8 @@
+use crate::tui::glyphs::Glyphs;
+use crate::tui::theme::Theme;
 use ::tui::buffer::Buffer;
 use ::tui::layout::Rect;
+use ::tui::style::Style;
 use ::tui::widgets::Widget;
 
 use log::debug;
@@ -7,51 +10,51 @@ use pewcraft_common::game::{Cell, Character, GameMap, Id, IdMap};
 
 struct CellCorner(char, char, char, char);
 impl CellCorner {
-    pub fn from_map_size(x: u16, y: u16, width: usize, height: usize) -> Self {
+    pub fn from_map_size(x: u16, y: u16, width: usize, height: usize, g: &Glyphs) -> Self {
         let max_x = width as u16 - 1;
         let max_y = height as u16 - 1;
 
         let tl = match (x, y) {
-            (0, 0) => '╔',
-            (0, _) => '╠',
-            (_, 0) => '╦',
-            (_, _) => '╬',
+            (0, 0) => g.top_left,
+            (0, _) => g.left,
+            (_, 0) => g.top,
+            (_, _) => g.cross,
         };
 
         let tr = if x == max_x {
             if y == 0 {
-                '╗'
+                g.top_right
             } else {
-                '╣'
+                g.right
             }
         } else if y == 0 {
-            '╦'
+            g.top
         } else {
-            '╬'
+            g.cross
         };
 
         let bl = if x == 0 {
             if y == max_y {
-                '╚'
+                g.bottom_left
             } else {
-                '╠'
+                g.left
             }
         } else if y == max_y {
-            '╩'
+            g.bottom
         } else {
-            '╬'
+            g.cross
         };
 
         let br = if x == max_x {
             if y == max_y {
-                '╝'
+                g.bottom_right
             } else {
-                '╣'
+                g.right
             }
         } else if y == max_y {
-            '╩'
+            g.bottom
         } else {
-            '╬'
+            g.cross
         };
 
         CellCorner(tl, tr, bl, br)
@@ -124,6 +127,7 @@ impl<'a> Display for FormatMapSelection<'a> {
 pub(super) struct FormatMap<'a>(
     pub(super) &'a GameMap,
     pub(super) Option<&'a IdMap<Character>>,
+    pub(super) &'a Theme,
 );
 impl<'a> FormatMap<'a> {
     fn character(&self, id: Id<Cell>) -> Option<&'a Character> {
@@ -137,57 +141,224 @@ impl<'a> FormatMap<'a> {
     }
 }
 
+fn spawn_team(map: &GameMap, id: Id<Cell>) -> Option<usize> {
+    map.teams
+        .iter()
+        .position(|(_, spawns)| spawns.contains(&id))
+}
+
+// Spawn positions are drawn in the color of their team, other cells in the one of their type
+fn cell_style(map: &GameMap, theme: &Theme, cell: &Cell, id: Id<Cell>) -> Style {
+    match spawn_team(map, id) {
+        Some(team) => theme.team(team),
+        None => theme.cell(&cell_type(cell)),
+    }
+}
+
+// The name of the Cell variant, e.g. "Wall" for `Wall { .. }`
+fn cell_type(cell: &Cell) -> String {
+    format!("{:?}", cell)
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
+}
+
 impl<'a> Widget for FormatMap<'a> {
     fn draw(&mut self, area: Rect, buf: &mut Buffer) {
         let map = &self.0;
-        let cell_width = area.width / map.width as u16;
-        let cell_height = area.height / map.height as u16;
-
-        assert!(
-            cell_width > 2 && cell_height > 2,
-            "Need at least a 3*3 cell size! Your screen is too small and/or the map too big..."
-        );
+        // a bigger panel or another arrangement can leave too little room for the cells: the map
+        // is still shown, one character per cell, until the room comes back
+        if !FormatMap::fits(map, area) {
+            Thumbnail(map, self.2).draw(area, buf);
+            return;
+        }
 
         let mut id = Id::new(0);
         for cell in map.data.iter() {
             let (x, y) = map.id_to_xy(id);
+            let glyphs = &self.2.glyphs;
             let (x, y) = (x as u16, y as u16);
-            let corners = CellCorner::from_map_size(x, y, map.width, map.height);
+            let corners = CellCorner::from_map_size(x, y, map.width, map.height, glyphs);
             debug!("Offset of curr cell: {}/{}", x, y);
 
-            let cell_rect = Rect::new(x * cell_width, y * cell_height, cell_width, cell_height);
+            let cell_rect = FormatMap::cell_rect(map, area, id);
             // get the character in the cell (if any)
-            let character = self.character(id);
+            let character = self.character(id).map(|c| (c, self.2.team(c.team.raw())));
+            let style = cell_style(map, self.2, cell, id);
 
-            FormatCell(cell, character, corners).draw(cell_rect, buf);
+            FormatCell(character, corners, glyphs, style).draw(cell_rect, buf);
 
             id = Id::new(id.raw() + 1);
         }
     }
 }
 
-struct FormatCell<'a>(&'a Cell, Option<&'a Character>, CellCorner);
+// Screen <-> map conversions, shared by the drawing and the mouse handling
+impl<'a> FormatMap<'a> {
+    fn cell_size(map: &GameMap, area: Rect) -> (u16, u16) {
+        (
+            area.width / map.width as u16,
+            area.height / map.height as u16,
+        )
+    }
+
+    // Whether there is room for cells of at least 3*3, their borders and their content
+    fn fits(map: &GameMap, area: Rect) -> bool {
+        let (cell_width, cell_height) = FormatMap::cell_size(map, area);
+        cell_width > 2 && cell_height > 2
+    }
+
+    pub(super) fn cell_rect(map: &GameMap, area: Rect, id: Id<Cell>) -> Rect {
+        let (cell_width, cell_height) = FormatMap::cell_size(map, area);
+        let (x, y) = map.id_to_xy(id);
+        Rect::new(
+            area.x + x as u16 * cell_width,
+            area.y + y as u16 * cell_height,
+            cell_width,
+            cell_height,
+        )
+    }
+
+    // Returns the cell drawn at the given screen position, if any
+    pub(super) fn cell_at(map: &GameMap, area: Rect, column: u16, row: u16) -> Option<Id<Cell>> {
+        // the thumbnail drawn instead is too small to aim at
+        if !FormatMap::fits(map, area) || column < area.x || row < area.y {
+            return None;
+        }
+        let (cell_width, cell_height) = FormatMap::cell_size(map, area);
+
+        let x = ((column - area.x) / cell_width) as usize;
+        let y = ((row - area.y) / cell_height) as usize;
+        if x >= map.width || y >= map.height {
+            None
+        } else {
+            Some(Id::new(y * map.width + x))
+        }
+    }
+}
+
+// The character in the cell (if any) comes with the style of its team
+struct FormatCell<'a>(
+    Option<(&'a Character, Style)>,
+    CellCorner,
+    &'a Glyphs,
+    Style,
+);
 impl<'a> Widget for FormatCell<'a> {
     fn draw(&mut self, area: Rect, buf: &mut Buffer) {
-        let cell = self.0;
-        let character = self.1;
-        let corners = &self.2;
+        let character = self.0;
+        let corners = &self.1;
+        let (h, v) = (self.2.horizontal, self.2.vertical);
+        let style = self.3;
+        let (left, right, top, bottom) = (area.left(), area.right(), area.top(), area.bottom());
 
         for x in 1..area.width {
-            let x = x + area.left();
-            buf.get_mut(x, area.top()).set_char('═');
-            buf.get_mut(x, area.bottom()).set_char('═');
+            let x = x + left;
+            buf.get_mut(x, top).set_char(h).set_style(style);
+            buf.get_mut(x, bottom).set_char(h).set_style(style);
         }
 
         for y in 1..area.height {
-            let y = y + area.top();
-            buf.get_mut(area.left(), y).set_char('║');
-            buf.get_mut(area.right(), y).set_char('║');
+            let y = y + top;
+            buf.get_mut(left, y).set_char(v).set_style(style);
+            buf.get_mut(right, y).set_char(v).set_style(style);
         }
 
-        buf.get_mut(area.left(), area.top()).set_char(corners.0);
-        buf.get_mut(area.right(), area.top()).set_char(corners.1);
-        buf.get_mut(area.left(), area.bottom()).set_char(corners.2);
-        buf.get_mut(area.right(), area.bottom()).set_char(corners.3);
+        let corners = [
+            (left, top, corners.0),
+            (right, top, corners.1),
+            (left, bottom, corners.2),
+            (right, bottom, corners.3),
+        ];
+        for (x, y, corner) in corners.iter() {
+            buf.get_mut(*x, *y).set_char(*corner).set_style(style);
+        }
+
+        // the initial of its name, in the middle of the cell
+        if let Some((character, team)) = character {
+            if let Some(initial) = character.name.chars().next() {
+                let (x, y) = (area.left() + area.width / 2, area.top() + area.height / 2);
+                buf.get_mut(x, y).set_char(initial).set_style(team);
+            }
+        }
+    }
+}
+
+// The whole map in a few characters, for screens where it does not fit cell by cell: spawn
+// positions show the number of their team, other cells the initial of their type. Maps larger
+// than the area only show one cell out of every `step`.
+pub(super) struct Thumbnail<'a>(pub(super) &'a GameMap, pub(super) &'a Theme);
+impl<'a> Widget for Thumbnail<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let (map, theme) = (self.0, self.1);
+        if area.width == 0 || area.height == 0 || map.width == 0 || map.height == 0 {
+            return;
+        }
+        let (width, height) = (area.width as usize, area.height as usize);
+        let step = ((map.width + width - 1) / width).max((map.height + height - 1) / height);
+        // terminal cells are about twice as tall as wide, cells are two columns wide when they fit
+        let columns = if step == 1 && map.width * 2 <= width {
+            2
+        } else {
+            1
+        };
+        let drawn_width = (map.width + step - 1) / step * columns;
+        let drawn_height = (map.height + step - 1) / step;
+        let left = area.x + (width - drawn_width) as u16 / 2;
+        let top = area.y + (height - drawn_height) as u16 / 2;
+
+        let mut id = Id::new(0);
+        for cell in map.data.iter() {
+            let (x, y) = map.id_to_xy(id);
+            if x % step == 0 && y % step == 0 {
+                let symbol = match spawn_team(map, id) {
+                    Some(team) => std::char::from_digit((team as u32 + 1) % 10, 10).unwrap(),
+                    None => cell_type(cell)
+                        .chars()
+                        .next()
+                        .map_or('?', |c| c.to_ascii_lowercase()),
+                };
+                let style = cell_style(map, theme, cell, id);
+                let column = left + (x / step * columns) as u16;
+                let row = top + (y / step) as u16;
+                for offset in 0..columns as u16 {
+                    buf.get_mut(column + offset, row)
+                        .set_char(symbol)
+                        .set_style(style);
+                }
+            }
+            id = Id::new(id.raw() + 1);
+        }
+    }
+}
+
+// Restyles the border of a cell drawn by `FormatMap`, e.g. for the cursor
+pub(super) struct CellHighlight<'a>(
+    pub(super) &'a GameMap,
+    pub(super) Id<Cell>,
+    pub(super) Style,
+);
+impl<'a> Widget for CellHighlight<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let (map, style) = (self.0, self.2);
+        if !FormatMap::fits(map, area) {
+            return;
+        }
+
+        let cell_rect = FormatMap::cell_rect(map, area, self.1);
+        let (left, right, top, bottom) = (
+            cell_rect.left(),
+            cell_rect.right(),
+            cell_rect.top(),
+            cell_rect.bottom(),
+        );
+        for x in left..=right {
+            buf.get_mut(x, top).set_style(style);
+            buf.get_mut(x, bottom).set_style(style);
+        }
+        for y in top..=bottom {
+            buf.get_mut(left, y).set_style(style);
+            buf.get_mut(right, y).set_style(style);
+        }
     }
 }
@@ -0,0 +1,108 @@
+use crate::tui::map::Thumbnail;
+use crate::tui::theme::Theme;
+use ::tui::buffer::Buffer;
+use ::tui::layout::{Constraint, Direction, Layout, Rect};
+use ::tui::widgets::{Block, Borders, Widget};
+use pewcraft_common::game::GameDefinition;
+use pewcraft_frontend::state::SelectMapDataImpl;
+
+const PREVIEW_BLOCK_TITLE: &str = "Preview";
+
+// The maps to choose from, one per line, next to a preview of the selected one
+pub(super) struct MapBrowser<'a>(
+    pub(super) &'a SelectMapDataImpl,
+    pub(super) &'a GameDefinition,
+    pub(super) &'a Theme,
+);
+impl<'a> Widget for MapBrowser<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let (data, g, theme) = (self.0, self.1, self.2);
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(area);
+
+        let title = if data.filtering {
+            format!(
+                "Maps | filter: {}_ | sort: {}",
+                data.filter,
+                data.sort.name()
+            )
+        } else if data.filter.is_empty() {
+            format!("Maps (/ to filter) | sort: {}", data.sort.name())
+        } else {
+            format!(
+                "Maps (/ to filter) | filter: {} | sort: {}",
+                data.filter,
+                data.sort.name()
+            )
+        };
+        let mut list = Block::default()
+            .title(&title)
+            .title_style(theme.title)
+            .borders(Borders::ALL)
+            .border_style(theme.border);
+        list.draw(chunks[0], buf);
+        let inner = list.inner(chunks[0]);
+
+        let mut preview = Block::default()
+            .title(PREVIEW_BLOCK_TITLE)
+            .title_style(theme.title)
+            .borders(Borders::ALL)
+            .border_style(theme.border);
+        preview.draw(chunks[1], buf);
+        if let Some(map) = data
+            .map_ids
+            .get(data.curr_id)
+            .and_then(|id| g.maps.get(*id))
+        {
+            Thumbnail(map, theme).draw(preview.inner(chunks[1]), buf);
+        }
+
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+        if data.map_ids.is_empty() {
+            let message = format!("No map matches '{}'.", data.filter);
+            buf.set_stringn(inner.x, inner.y, &message, inner.width as usize, theme.hint);
+            return;
+        }
+
+        let maps: Vec<_> = data
+            .map_ids
+            .iter()
+            .filter_map(|id| g.maps.get(*id))
+            .collect();
+        let name_width = maps
+            .iter()
+            .map(|m| m.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        // the selected map is always visible
+        let page = inner.height as usize;
+        let offset = (data.curr_id + 1).saturating_sub(page);
+        for (row, (index, map)) in maps.iter().enumerate().skip(offset).take(page).enumerate() {
+            let favorite = if data.favorites.contains(map) {
+                theme.glyphs.favorite
+            } else {
+                ' '
+            };
+            let line = format!(
+                "{} {:nw$}  {:>3}x{:<3}  {} teams",
+                favorite,
+                map.name,
+                map.width,
+                map.height,
+                map.teams.len(),
+                nw = name_width
+            );
+            let style = if index == data.curr_id {
+                theme.selection
+            } else {
+                theme.text
+            };
+            let y = inner.y + row as u16;
+            buf.set_stringn(inner.x, y, &line, inner.width as usize, style);
+        }
+    }
+}
@@ -1,6 +1,26 @@
 mod tui;
 pub use self::tui::*;
 
+pub use self::fatal::fatal_screen;
+pub use self::glyphs::GlyphSet;
+pub use self::keymap::Keymap;
+pub use self::theme::Theme;
+
+mod clipboard;
+mod debug;
+mod dialog;
+mod encyclopedia;
+mod fatal;
+mod glyphs;
+mod help;
+mod input;
+mod keymap;
+mod log_console;
 mod map;
+mod map_browser;
+mod overlay;
+mod panel;
+mod panes;
 mod render;
-mod input;
+mod status;
+mod theme;
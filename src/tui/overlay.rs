@@ -0,0 +1,67 @@
+use crate::tui::encyclopedia::Encyclopedia;
+use crate::tui::log_console::LogConsole;
+use crate::tui::panel::Scroll;
+use ::tui::buffer::Buffer;
+use ::tui::layout::Rect;
+use ::tui::widgets::Widget;
+use std::time::Duration;
+
+// What is drawn on top of the current screen, independently of the game state
+pub(super) struct Overlays {
+    pub(super) help: bool,
+    pub(super) log_console: LogConsole,
+    pub(super) debug: bool,
+    pub(super) encyclopedia: Option<Encyclopedia>,
+    pub(super) sheet: bool,
+    // shared by the encyclopedia and the character sheet, which are never shown together
+    pub(super) scroll: Scroll,
+    // how long drawing the previous frame took, shown in the debug overlay
+    pub(super) frame_time: Duration,
+}
+
+impl Overlays {
+    pub(super) fn new() -> Self {
+        Overlays {
+            help: false,
+            log_console: LogConsole::new(),
+            debug: false,
+            encyclopedia: None,
+            sheet: false,
+            scroll: Scroll::default(),
+            frame_time: Duration::default(),
+        }
+    }
+
+    // Whether anything covers the screen, the screen below does not get the clicks then
+    pub(super) fn any_visible(&self) -> bool {
+        self.help
+            || self.log_console.visible
+            || self.debug
+            || self.encyclopedia.is_some()
+            || self.sheet
+    }
+}
+
+// Blanks the area, so that an overlay does not show what is drawn below it
+pub(super) struct Clear;
+impl Widget for Clear {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        for x in area.left()..area.right() {
+            for y in area.top()..area.bottom() {
+                buf.get_mut(x, y).reset();
+            }
+        }
+    }
+}
+
+// Returns a rectangle of the given size (in percent of the area) centered in the area
+pub(super) fn centered(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let width = (u32::from(area.width) * u32::from(percent_x) / 100) as u16;
+    let height = (u32::from(area.height) * u32::from(percent_y) / 100) as u16;
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
@@ -0,0 +1,292 @@
+use crate::tui::theme::Theme;
+use ::tui::buffer::Buffer;
+use ::tui::layout::Rect;
+use ::tui::style::Style;
+use ::tui::widgets::{Block, Borders, Text, Widget};
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+// Where the info panel is scrolled to. Kept across frames by the Tui, while the panel writes back
+// what it learns when drawn: the size of a page, and whether it shows something else.
+#[derive(Debug, Default)]
+pub(super) struct Scroll {
+    offset: Cell<u16>,
+    // visible lines, the last time the panel was drawn
+    page: Cell<u16>,
+    // hash of what the text is about: another topic is shown from its beginning, while the same
+    // one keeps its place when its text changes (the game is refreshed every half second)
+    topic: Cell<u64>,
+    // where the text was drawn, and the line of the text shown on each row, for mouse clicks
+    inner: Cell<Rect>,
+    rows: RefCell<Vec<usize>>,
+}
+
+impl Scroll {
+    // A page minus one line, so that the reader keeps some context
+    pub(super) fn page_down(&self) {
+        let step = self.page.get().saturating_sub(1).max(1);
+        self.offset.set(self.offset.get().saturating_add(step));
+    }
+
+    pub(super) fn page_up(&self) {
+        let step = self.page.get().saturating_sub(1).max(1);
+        self.offset.set(self.offset.get().saturating_sub(step));
+    }
+
+    // Called before each frame: until the panel is drawn again, nothing can be clicked in it
+    pub(super) fn forget_rows(&self) {
+        self.inner.set(Rect::default());
+        self.rows.borrow_mut().clear();
+    }
+
+    // The line of the text (counting its newlines, not the wrapping) drawn at this position
+    pub(super) fn line_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = self.inner.get();
+        if column < inner.left() || column >= inner.right() || row < inner.top() {
+            return None;
+        }
+        self.rows.borrow().get((row - inner.y) as usize).copied()
+    }
+}
+
+type Line = Vec<(String, Style)>;
+
+// Splits the text on its newlines, keeping the style of each piece
+fn logical_lines(text: &[Text], default: Style) -> Vec<Line> {
+    let mut lines = vec![Line::new()];
+    for t in text {
+        let (content, style) = match t {
+            Text::Raw(content) => (content, default),
+            Text::Styled(content, style) => (content, *style),
+        };
+        for (index, piece) in content.split('\n').enumerate() {
+            if index > 0 {
+                lines.push(Line::new());
+            }
+            if !piece.is_empty() {
+                lines.last_mut().unwrap().push((piece.to_owned(), style));
+            }
+        }
+    }
+    lines
+}
+
+// Words and runs of spaces, so that lines break between words
+fn tokens(line: &Line) -> Vec<(String, Style)> {
+    let mut tokens: Vec<(String, Style)> = Vec::new();
+    for (content, style) in line {
+        for c in content.chars() {
+            match tokens.last_mut() {
+                Some((token, s)) if *s == *style && token.starts_with(' ') == (c == ' ') => {
+                    token.push(c)
+                }
+                _ => tokens.push((c.to_string(), *style)),
+            }
+        }
+    }
+    tokens
+}
+
+// Word wraps the lines to the width (in columns, wide glyphs take two), words longer than a line
+// are cut
+fn wrap(lines: &[Line], width: usize) -> Vec<Line> {
+    let mut wrapped = Vec::new();
+    for line in lines {
+        let mut current = Line::new();
+        let mut length = 0;
+        // the indentation of a line is kept, the spaces at a line break are not
+        let mut continued = false;
+        for (token, style) in tokens(line) {
+            let size = token.width();
+            if token.starts_with(' ') {
+                if length + size > width {
+                    wrapped.push(std::mem::take(&mut current));
+                    length = 0;
+                    continued = true;
+                } else if length > 0 || !continued {
+                    current.push((token, style));
+                    length += size;
+                }
+                continue;
+            }
+
+            if length > 0 && length + size > width {
+                wrapped.push(std::mem::take(&mut current));
+                length = 0;
+                continued = true;
+            }
+            let mut piece = String::new();
+            for c in token.chars() {
+                let size = c.width().unwrap_or(0);
+                // a glyph wider than the whole line still gets a line of its own
+                if length > 0 && length + size > width {
+                    if !piece.is_empty() {
+                        current.push((std::mem::take(&mut piece), style));
+                    }
+                    wrapped.push(std::mem::take(&mut current));
+                    length = 0;
+                    continued = true;
+                }
+                piece.push(c);
+                length += size;
+            }
+            current.push((piece, style));
+        }
+        wrapped.push(current);
+    }
+    wrapped
+}
+
+fn hash(topic: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    topic.hash(&mut hasher);
+    hasher.finish()
+}
+
+// A bordered block of text, word wrapped to its width. When the text does not fit, it scrolls
+// (see Scroll) and a scrollbar is drawn on the right border.
+pub(super) struct InfoPanel<'a> {
+    pub(super) title: &'a str,
+    // what the text is about, see Scroll
+    pub(super) topic: &'a str,
+    pub(super) text: &'a [Text<'a>],
+    pub(super) scroll: &'a Scroll,
+    pub(super) theme: &'a Theme,
+}
+
+impl<'a> Widget for InfoPanel<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme;
+        let mut block = Block::default()
+            .title(self.title)
+            .title_style(theme.title)
+            .borders(Borders::ALL)
+            .border_style(theme.border);
+        block.draw(area, buf);
+        let inner = block.inner(area);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        let topic = hash(self.topic);
+        if topic != self.scroll.topic.get() {
+            self.scroll.topic.set(topic);
+            self.scroll.offset.set(0);
+        }
+        let lines = logical_lines(self.text, theme.text);
+        // which line of the text each wrapped line comes from
+        let mut sources = Vec::new();
+        let lines: Vec<Line> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(index, line)| {
+                let wrapped = wrap(std::slice::from_ref(line), inner.width as usize);
+                sources.extend(std::iter::repeat(index).take(wrapped.len()));
+                wrapped
+            })
+            .collect();
+
+        let page = inner.height;
+        let max_offset = (lines.len() as u16).saturating_sub(page);
+        let offset = self.scroll.offset.get().min(max_offset);
+        self.scroll.offset.set(offset);
+        self.scroll.page.set(page);
+        self.scroll.inner.set(inner);
+        *self.scroll.rows.borrow_mut() = sources
+            .into_iter()
+            .skip(offset as usize)
+            .take(page as usize)
+            .collect();
+
+        for (row, line) in lines
+            .iter()
+            .skip(offset as usize)
+            .take(page as usize)
+            .enumerate()
+        {
+            let mut x = inner.x;
+            let y = inner.y + row as u16;
+            for (content, style) in line {
+                let width = (inner.right() - x) as usize;
+                let (next, _) = buf.set_stringn(x, y, content, width, *style);
+                x = next;
+            }
+        }
+
+        // the thumb shows which part of the text is visible, along the right border
+        if max_offset > 0 {
+            let (page, total) = (u32::from(page), lines.len() as u32);
+            let size = (page * page / total).max(1);
+            let start = (page - size) * u32::from(offset) / u32::from(max_offset);
+            let (size, start) = (size as u16, start as u16);
+            let x = area.right() - 1;
+            for y in inner.y + start..inner.y + start + size {
+                buf.get_mut(x, y)
+                    .set_char(theme.glyphs.thumb)
+                    .set_style(theme.border);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::tui::style::Color;
+
+    fn wrap_str(text: &str, width: usize) -> Vec<String> {
+        let lines = logical_lines(&[Text::raw(text)], Style::default());
+        wrap(&lines, width)
+            .iter()
+            .map(|line| line.iter().map(|(content, _)| content.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn short_lines_are_kept() {
+        assert_eq!(wrap_str("one two", 10), vec!["one two"]);
+        assert_eq!(wrap_str("one\n\ntwo", 10), vec!["one", "", "two"]);
+    }
+
+    #[test]
+    fn lines_break_between_words() {
+        assert_eq!(wrap_str("one two three", 7), vec!["one two", "three"]);
+        // the spaces at the break are dropped
+        assert_eq!(wrap_str("one    two", 5), vec!["one", "two"]);
+        assert_eq!(wrap_str("one two", 3), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn indentation_is_kept_on_the_first_line_only() {
+        assert_eq!(wrap_str("    one two", 7), vec!["    one", "two"]);
+    }
+
+    #[test]
+    fn long_words_are_cut() {
+        assert_eq!(wrap_str("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(wrap_str("ab cdefgh", 2), vec!["ab", "cd", "ef", "gh"]);
+    }
+
+    #[test]
+    fn wide_glyphs_take_two_columns() {
+        assert_eq!(wrap_str("日本語 ok", 6), vec!["日本語", "ok"]);
+        assert_eq!(wrap_str("日本語", 4), vec!["日本", "語"]);
+        // an odd width cannot take the last half of a glyph
+        assert_eq!(wrap_str("日本語", 5), vec!["日本", "語"]);
+        // nor can a line narrower than a glyph, which still shows it rather than loop
+        assert_eq!(wrap_str("日本", 1), vec!["日", "本"]);
+    }
+
+    #[test]
+    fn styles_are_kept_across_breaks() {
+        let red = Style::default().fg(Color::Red);
+        let text = [Text::raw("one "), Text::styled("two three", red)];
+        let lines = logical_lines(&text, Style::default());
+        let wrapped = wrap(&lines, 7);
+        assert_eq!(wrapped.len(), 2);
+        assert_eq!(wrapped[0].last(), Some(&("two".to_owned(), red)));
+        assert_eq!(wrapped[1], vec![("three".to_owned(), red)]);
+    }
+}
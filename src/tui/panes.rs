@@ -0,0 +1,145 @@
+use crate::tui::status::ConnectionIndicator;
+use ::tui::layout::{Constraint, Direction, Layout, Rect};
+use log::{info, warn};
+use pewcraft_frontend::config;
+use serde::{Deserialize, Serialize};
+
+const PANES_FILE: &str = "layout.json";
+
+// Share of the screen the info panel can take, in percent
+const MIN_PANEL: u16 = 10;
+const MAX_PANEL: u16 = 70;
+const PANEL_STEP: u16 = 5;
+
+// Terminal cells are about twice as tall as wide: past this ratio of columns to rows, the
+// screen is wide enough for the panel to go at the right of the map
+const WIDE_RATIO: u16 = 3;
+// and below this width, a side panel would be too narrow to read
+const MIN_SIDE_WIDTH: u16 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Arrangement {
+    // side or stacked, depending on the shape of the terminal
+    Auto,
+    Side,
+    Stacked,
+}
+
+// How the screen is shared between the map and the info panel.
+// Changed from the keyboard, and saved as a preference for the next runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Panes {
+    arrangement: Arrangement,
+    // in percent of the screen
+    panel: u16,
+    // the panel is hidden, the map takes the whole screen
+    collapsed: bool,
+}
+
+impl Default for Panes {
+    fn default() -> Self {
+        Panes {
+            arrangement: Arrangement::Auto,
+            panel: 25,
+            collapsed: false,
+        }
+    }
+}
+
+impl Panes {
+    pub(super) fn load() -> Self {
+        match config::read_data::<Panes>(PANES_FILE) {
+            Ok(panes) => panes.unwrap_or_default(),
+            Err(e) => {
+                warn!("Could not load the layout: {}", e);
+                Panes::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        match config::write_data(PANES_FILE, self) {
+            Ok(()) => info!("Layout stored: {:?}", self),
+            Err(e) => warn!("Could not store the layout: {}", e),
+        }
+    }
+
+    // Auto -> Side -> Stacked -> Auto
+    pub(super) fn cycle_arrangement(&mut self) {
+        self.arrangement = match self.arrangement {
+            Arrangement::Auto => Arrangement::Side,
+            Arrangement::Side => Arrangement::Stacked,
+            Arrangement::Stacked => Arrangement::Auto,
+        };
+        self.save();
+    }
+
+    pub(super) fn toggle_panel(&mut self) {
+        self.collapsed = !self.collapsed;
+        self.save();
+    }
+
+    // Growing a collapsed panel shows it again
+    pub(super) fn grow_panel(&mut self) {
+        if self.collapsed {
+            self.collapsed = false;
+        } else {
+            self.panel = (self.panel + PANEL_STEP).min(MAX_PANEL);
+        }
+        self.save();
+    }
+
+    pub(super) fn shrink_panel(&mut self) {
+        self.panel = self.panel.saturating_sub(PANEL_STEP).max(MIN_PANEL);
+        self.save();
+    }
+
+    fn direction(&self, area: Rect) -> Direction {
+        let side = match self.arrangement {
+            Arrangement::Side => true,
+            Arrangement::Stacked => false,
+            Arrangement::Auto => {
+                area.width >= MIN_SIDE_WIDTH && area.width >= WIDE_RATIO * area.height
+            }
+        };
+        if side {
+            Direction::Horizontal
+        } else {
+            Direction::Vertical
+        }
+    }
+
+    // Returns the map area, the info panel, the hint bar and the connection status.
+    // The info panel is empty when collapsed.
+    pub(super) fn split(&self, area: Rect) -> Vec<Rect> {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(area);
+        let panel = if self.collapsed {
+            Constraint::Length(0)
+        } else {
+            Constraint::Percentage(self.panel)
+        };
+        let mut chunks = Layout::default()
+            .direction(self.direction(outer[0]))
+            .margin(1)
+            .constraints([Constraint::Min(0), panel].as_ref())
+            .split(outer[0]);
+        chunks.extend(
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Min(0),
+                        Constraint::Length(ConnectionIndicator::WIDTH),
+                    ]
+                    .as_ref(),
+                )
+                .split(outer[1]),
+        );
+        chunks
+    }
+}
@@ -1,45 +1,198 @@
-use crate::state::{
-    CreateCharacterState, CreateOrJoinState, GlobalState, PlayGameState, SelectMapData,
-};
-use crate::tui::map::FormatMap;
+use crate::tui::debug::DebugOverlay;
+use crate::tui::dialog::Dialog;
+use crate::tui::encyclopedia::{CharacterSheet, EncyclopediaOverlay};
+use crate::tui::glyphs::Redraw;
+use crate::tui::help::{HelpOverlay, HintBar};
+use crate::tui::keymap::Keymap;
+use crate::tui::log_console::LogView;
+use crate::tui::map::{CellHighlight, FormatMap};
+use crate::tui::map_browser::MapBrowser;
+use crate::tui::overlay::Overlays;
+use crate::tui::panel::{InfoPanel, Scroll};
+use crate::tui::panes::Panes;
+use crate::tui::status::ConnectionIndicator;
+use crate::tui::theme::Theme;
 use log::{debug, info};
-use pewcraft_common::game::GameDefinition;
-use tui::layout::Alignment;
-use tui::layout::{Constraint, Direction, Layout};
-use tui::style::{Modifier, Style};
-use tui::widgets::{Block, Borders, Widget};
-use tui::widgets::{Paragraph, Text};
+use pewcraft_common::game::{Cell, GameDefinition, GameMap, Id};
+use pewcraft_frontend::api::{ConnectionStatus, WireAction};
+use pewcraft_frontend::state::{
+    CreateCharacterState, CreateOrJoinState, GameOverData, GlobalState, PlayGameState,
+    SelectMapData,
+};
+use tui::layout::Rect;
+use tui::widgets::{Text, Widget};
 use tui::Frame;
 
 const SELECT_MAP_BLOCK_TITLE: &str = "Select map";
 const CREATE_CHAR_BLOCK_TITLE: &str = "Create your character";
+const PLAY_GAME_BLOCK_TITLE: &str = "Game";
+const GAME_OVER_BLOCK_TITLE: &str = "Game over";
+
+// What lies under the mouse cursor
+pub enum ClickTarget {
+    Option(usize),
+    Cell(Id<Cell>),
+}
+
+// Hit-test for mouse clicks, against the frame `render` drew last for the same state
+pub fn click_target(
+    area: Rect,
+    s: &GlobalState,
+    g: &GameDefinition,
+    panes: &Panes,
+    scroll: &Scroll,
+    column: u16,
+    row: u16,
+) -> Option<ClickTarget> {
+    let chunks = panes.split(area);
+
+    if let GlobalState::CreateOrJoin(_) = s {
+        // CREATE is on the first line of the text, JOIN on the second one
+        return match scroll.line_at(column, row) {
+            Some(line @ 0..=1) => Some(ClickTarget::Option(line)),
+            _ => None,
+        };
+    }
+
+    displayed_map(s, g)
+        .and_then(|map| FormatMap::cell_at(map, chunks[0], column, row))
+        .map(ClickTarget::Cell)
+}
+
+fn displayed_map<'m>(s: &'m GlobalState, g: &'m GameDefinition) -> Option<&'m GameMap> {
+    match s {
+        // only a preview of the map is drawn
+        GlobalState::SelectMap(_) => None,
+        GlobalState::CreateCharacter(c) => match c {
+            CreateCharacterState::Team(c)
+            | CreateCharacterState::Class(c)
+            | CreateCharacterState::Position(c)
+            | CreateCharacterState::Name(c) => Some(c.curr().map),
+        },
+        GlobalState::PlayGame(play) => match play {
+            PlayGameState::OurTurn(p) | PlayGameState::NotOurTurn(p) => {
+                g.maps.get(p.curr().game_state.map)
+            }
+        },
+        GlobalState::GameOver(o) => Some(o.curr().map),
+        GlobalState::ConfirmExit(c) => displayed_map(c.prev(), g),
+        GlobalState::CreateOrJoin(_) | GlobalState::WaitForGameCreation(_) | GlobalState::Exit => {
+            None
+        }
+    }
+}
+
+// What the info panel is about: browsing to another class or map shows its text from the top,
+// while refreshing the same game keeps the place of the reader
+fn topic(s: &GlobalState) -> String {
+    match s {
+        GlobalState::CreateCharacter(CreateCharacterState::Class(c)) => {
+            let class = c.curr().classes.get(c.curr().class_index);
+            format!("{} {:?}", s.variant(), class.map(|id| id.raw()))
+        }
+        GlobalState::SelectMap(m) => {
+            let map = m.curr().map_ids.get(m.curr().curr_id);
+            format!("{} {:?}", s.variant(), map.map(|id| id.raw()))
+        }
+        _ => s.variant().to_owned(),
+    }
+}
+
+// What the user chose for the look and feel of the client
+#[derive(Clone, Copy)]
+pub struct Prefs<'a> {
+    pub keymap: &'a Keymap,
+    pub theme: &'a Theme,
+    pub panes: &'a Panes,
+}
 
 pub struct Renderer<'a, 'b, 'c, B: tui::backend::Backend> {
     f: &'a mut Frame<'c, B>,
     s: &'a GlobalState<'b>,
     g: &'a GameDefinition,
+    theme: &'a Theme,
+    scroll: &'a Scroll,
     chunks: Vec<tui::layout::Rect>,
 }
 
 impl<'a, 'b, 'c, B: tui::backend::Backend> Renderer<'a, 'b, 'c, B> {
-    pub fn render(f: &'a mut Frame<'c, B>, s: &'a GlobalState<'b>, g: &'a GameDefinition) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
-            .split(f.size());
-
-        Renderer { f, s, g, chunks }.render_impl();
+    pub fn render(
+        f: &'a mut Frame<'c, B>,
+        s: &'a GlobalState<'b>,
+        g: &'a GameDefinition,
+        prefs: Prefs<'a>,
+        scroll: &'a Scroll,
+        overlays: &'a Overlays,
+        status: ConnectionStatus,
+    ) {
+        let Prefs {
+            keymap,
+            theme,
+            panes,
+        } = prefs;
+        let area = f.size();
+        let chunks = panes.split(area);
+        let hint_bar = chunks[2];
+        let status_bar = chunks[3];
+        scroll.forget_rows();
+        Renderer {
+            f: &mut *f,
+            s,
+            g,
+            theme,
+            scroll,
+            chunks,
+        }
+        .render_impl();
+
+        HintBar(s, keymap, theme).render(f, hint_bar);
+        ConnectionIndicator(status, theme).render(f, status_bar);
+        if let GlobalState::ConfirmExit(c) = s {
+            let message = match **c.prev() {
+                GlobalState::CreateCharacter(_) => "Abandon the creation of your character?",
+                _ => "Leave the game? You will be able to come back with your character login.",
+            };
+            Dialog {
+                title: "Quit",
+                message,
+                options: &["Quit", "Stay"],
+                selected: if c.curr().leave { 0 } else { 1 },
+                theme,
+            }
+            .render(f, area);
+        }
+        if overlays.log_console.visible {
+            LogView(&overlays.log_console, theme).render(f, area);
+        }
+        if overlays.debug {
+            DebugOverlay(s, overlays.frame_time, theme).render(f, area);
+        }
+        if let Some(encyclopedia) = &overlays.encyclopedia {
+            EncyclopediaOverlay(encyclopedia, g, &overlays.scroll, theme).render(f, area);
+        }
+        if overlays.sheet {
+            CharacterSheet(s, g, &overlays.scroll, theme).render(f, area);
+        }
+        if overlays.help {
+            HelpOverlay(s, keymap, theme).render(f, area);
+        }
+        Redraw(&theme.glyphs).render(f, area);
     }
 
-    fn invert_text() -> Style {
-        let style = Style::default();
-        let fg = style.fg;
-        let bg = style.bg;
-        style.bg(fg).fg(bg)
+    // Every info block goes through this one, so that its text wraps and scrolls
+    fn panel(&mut self, title: &str, text: &[Text]) {
+        let area = self.chunks[1];
+        InfoPanel {
+            title,
+            topic: &topic(self.s),
+            text,
+            scroll: self.scroll,
+            theme: self.theme,
+        }
+        .render(self.f, area);
     }
 
-    fn render_impl(self) {
+    fn render_impl(mut self) {
         match self.s {
             GlobalState::CreateOrJoin(create_or_join) => {
                 self.create_or_join(create_or_join);
@@ -54,66 +207,69 @@ impl<'a, 'b, 'c, B: tui::backend::Backend> Renderer<'a, 'b, 'c, B> {
             GlobalState::PlayGame(play_game) => {
                 self.play_game(play_game);
             }
+            GlobalState::GameOver(game_over) => {
+                self.game_over(game_over);
+            }
             // TODO
             GlobalState::WaitForGameCreation(game_state) => {
                 let full_login =
                     format!("{}/{}", game_state.curr().game_id, game_state.curr().login);
-                Block::default()
-                    .title(&format!(
-                        "Waiting for other players | Character login: {}",
-                        full_login
-                    ))
-                    .borders(Borders::ALL)
-                    .render(self.f, self.chunks[1]);
+                let title = format!(
+                    "Waiting for other players | Character login: {}",
+                    full_login
+                );
+                self.panel(&title, &[]);
+            }
+            // the dialog itself is drawn on top of the interrupted state by `render`
+            GlobalState::ConfirmExit(c) => {
+                Renderer {
+                    f: self.f,
+                    s: &**c.prev(),
+                    g: self.g,
+                    theme: self.theme,
+                    scroll: self.scroll,
+                    chunks: self.chunks,
+                }
+                .render_impl();
             }
             GlobalState::Exit => panic!("Should not try to render when in the 'Exit' state"),
         };
     }
 
-    fn create_or_join(self, create_or_join: &CreateOrJoinState) {
+    fn create_or_join(mut self, create_or_join: &CreateOrJoinState) {
         let text = match create_or_join {
             CreateOrJoinState::Create(s) => [
                 Text::raw("    "),
-                Text::styled("CREATE", Self::invert_text()),
+                Text::styled("CREATE", self.theme.selection),
                 Text::raw("\n or "),
                 Text::raw("JOIN"),
-                Text::raw("Login: "),
+                Text::raw("\n    Login: "),
                 Text::raw(&s.curr().login),
             ],
             CreateOrJoinState::Join(s) => [
                 Text::raw("    "),
                 Text::raw("CREATE"),
                 Text::raw("\n or "),
-                Text::styled("JOIN", Self::invert_text()),
-                Text::raw("Login: "),
+                Text::styled("JOIN", self.theme.selection),
+                Text::raw("\n    Login: "),
                 Text::raw(&s.curr().login),
             ],
         };
 
-        Paragraph::new(text.iter())
-            .block(
-                Block::default()
-                    .title(CREATE_CHAR_BLOCK_TITLE)
-                    .borders(Borders::ALL),
-            )
-            .alignment(Alignment::Left)
-            .render(self.f, self.chunks[1]);
+        self.panel(CREATE_CHAR_BLOCK_TITLE, &text);
     }
 
-    fn create_character(self, create_character: &CreateCharacterState) {
+    fn create_character(mut self, create_character: &CreateCharacterState) {
         let map = match create_character {
             CreateCharacterState::Class(s) => {
                 let curr_id = s.curr().class_index;
                 let class_ids = &s.curr().classes;
-                let class = self
-                    .g
-                    .classes
-                    .get(*class_ids.get(curr_id).unwrap())
-                    .unwrap();
+                let g = self.g;
+                let class = g.classes.get(*class_ids.get(curr_id).unwrap()).unwrap();
                 let text = [
                     Text::styled(
                         format!("    {} / {}", curr_id + 1, class_ids.len()),
-                        Style::default().modifier(Modifier::BOLD),
+                        self.theme.title,
                     ),
                     Text::raw("\n    Name:         "),
                     Text::raw(&class.name),
@@ -121,14 +277,7 @@ impl<'a, 'b, 'c, B: tui::backend::Backend> Renderer<'a, 'b, 'c, B> {
                     Text::raw(&class.description),
                 ];
 
-                Paragraph::new(text.iter())
-                    .block(
-                        Block::default()
-                            .title(CREATE_CHAR_BLOCK_TITLE)
-                            .borders(Borders::ALL),
-                    )
-                    .alignment(Alignment::Left)
-                    .render(self.f, self.chunks[1]);
+                self.panel(CREATE_CHAR_BLOCK_TITLE, &text);
                 s.curr().map
             }
             CreateCharacterState::Name(s) => {
@@ -136,14 +285,7 @@ impl<'a, 'b, 'c, B: tui::backend::Backend> Renderer<'a, 'b, 'c, B> {
                     "    Now type your name: {}",
                     s.curr().name
                 ))];
-                Paragraph::new(text.iter())
-                    .block(
-                        Block::default()
-                            .title(CREATE_CHAR_BLOCK_TITLE)
-                            .borders(Borders::ALL),
-                    )
-                    .alignment(Alignment::Left)
-                    .render(self.f, self.chunks[1]);
+                self.panel(CREATE_CHAR_BLOCK_TITLE, &text);
                 s.curr().map
             }
             CreateCharacterState::Team(s) => {
@@ -158,21 +300,14 @@ impl<'a, 'b, 'c, B: tui::backend::Backend> Renderer<'a, 'b, 'c, B> {
                 let text = [
                     Text::styled(
                         format!("    {} / {}", curr_id + 1, team_ids.len()),
-                        Style::default().modifier(Modifier::BOLD),
+                        self.theme.title,
                     ),
                     Text::raw("\n    Name:         "),
                     Text::raw(&team.0),
                     Text::raw("\n    TODO store the nb of players, and what classes are already taken etc. OR EVEN BETTER<, SHOW THEM ON THE MAP!")
                 ];
 
-                Paragraph::new(text.iter())
-                    .block(
-                        Block::default()
-                            .title(CREATE_CHAR_BLOCK_TITLE)
-                            .borders(Borders::ALL),
-                    )
-                    .alignment(Alignment::Left)
-                    .render(self.f, self.chunks[1]);
+                self.panel(CREATE_CHAR_BLOCK_TITLE, &text);
                 s.curr().map
             }
             CreateCharacterState::Position(s) => {
@@ -184,40 +319,50 @@ impl<'a, 'b, 'c, B: tui::backend::Backend> Renderer<'a, 'b, 'c, B> {
                 let text = [
                     Text::styled(
                         format!("    {} / {}", curr_id + 1, positions.len()),
-                        Style::default().modifier(Modifier::BOLD),
+                        self.theme.title,
                     ),
                     Text::raw("\n    Initial position:         "),
                     Text::raw(format!("X: {}", x)),
                     Text::raw(format!("Y: {}", y)),
                 ];
 
-                Paragraph::new(text.iter())
-                    .block(
-                        Block::default()
-                            .title(CREATE_CHAR_BLOCK_TITLE)
-                            .borders(Borders::ALL),
-                    )
-                    .alignment(Alignment::Left)
-                    .render(self.f, self.chunks[1]);
+                self.panel(CREATE_CHAR_BLOCK_TITLE, &text);
                 s.curr().map
             }
         };
-        FormatMap(map, None).render(self.f, self.chunks[0]);
+        FormatMap(map, None, self.theme).render(self.f, self.chunks[0]);
     }
 
-    fn select_map(self, s: &SelectMapData) {
+    fn select_map(mut self, s: &SelectMapData) {
+        let g = self.g;
+        MapBrowser(s.curr(), g, self.theme).render(self.f, self.chunks[0]);
+
         let map_ids = &s.curr().map_ids;
         let curr_id = s.curr().curr_id;
-        let map = self.g.maps.get(*map_ids.get(curr_id).unwrap()).unwrap();
-        FormatMap(map, None).render(self.f, self.chunks[0]);
+        let map = match map_ids.get(curr_id).and_then(|id| g.maps.get(*id)) {
+            Some(map) => map,
+            None => {
+                let text = [Text::raw(format!(
+                    "    None of the {} maps matches the filter.",
+                    g.maps.ids().len()
+                ))];
+                self.panel(SELECT_MAP_BLOCK_TITLE, &text);
+                return;
+            }
+        };
 
-        let text = [
+        let favorite = if s.curr().favorites.contains(map) {
+            " (favorite)"
+        } else {
+            ""
+        };
+        let mut text = vec![
             Text::styled(
                 format!("    {} / {}", curr_id + 1, map_ids.len()),
-                Style::default().modifier(Modifier::BOLD),
+                self.theme.title,
             ),
             Text::raw("\n    Name:      "),
-            Text::raw(&map.name),
+            Text::raw(format!("{}{}", map.name, favorite)),
             Text::raw("\n    Width:     "),
             Text::raw(format!("{}", map.width)),
             Text::raw("\n    Height:    "),
@@ -225,28 +370,96 @@ impl<'a, 'b, 'c, B: tui::backend::Backend> Renderer<'a, 'b, 'c, B> {
             Text::raw("\n    Max teams: "),
             Text::raw(format!("{}", map.teams.len())),
         ];
+        for (index, (name, spawns)) in map.teams.iter().enumerate() {
+            text.push(Text::raw("\n      "));
+            text.push(Text::styled(
+                format!("{} ", index + 1),
+                self.theme.team(index),
+            ));
+            text.push(Text::raw(format!(
+                "{}: {} spawn positions",
+                name,
+                spawns.len()
+            )));
+        }
 
-        Paragraph::new(text.iter())
-            .block(
-                Block::default()
-                    .title(SELECT_MAP_BLOCK_TITLE)
-                    .borders(Borders::ALL),
-            )
-            .alignment(Alignment::Left)
-            .render(self.f, self.chunks[1]);
+        self.panel(SELECT_MAP_BLOCK_TITLE, &text);
     }
 
-    fn play_game(self, s: &PlayGameState) {
-        match s {
-            PlayGameState::NotOurTurn(s) | PlayGameState::OurTurn(s) => {
-                let map = s.prev().game.maps.get(s.curr().game_state.map).unwrap();
-                FormatMap(map, None).render(self.f, self.chunks[0]);
+    fn play_game(mut self, s: &PlayGameState) {
+        let (our_turn, s) = match s {
+            PlayGameState::OurTurn(s) => (true, s),
+            PlayGameState::NotOurTurn(s) => (false, s),
+        };
+        let play = s.curr();
+        let characters = &play.game_state.characters;
+        let map = s.prev().game.maps.get(play.game_state.map).unwrap();
+        FormatMap(map, Some(characters), self.theme).render(self.f, self.chunks[0]);
+        CellHighlight(map, play.cell, self.theme.selection).render(self.f, self.chunks[0]);
+
+        let turn = match play.progress.turn.and_then(|id| characters.get(id)) {
+            _ if our_turn => "Your turn".to_owned(),
+            Some(c) => format!("{}'s turn", c.name),
+            None => "Waiting for the next turn".to_owned(),
+        };
+        let mut text = vec![Text::styled(format!("    {}", turn), self.theme.title)];
+
+        let (x, y) = map.id_to_xy(play.cell);
+        text.push(Text::raw(format!("\n    Cursor:   X: {} Y: {}", x, y)));
+        let occupant = characters.iter().find(|(_, c)| c.position == play.cell);
+        if let Some((id, c)) = occupant {
+            let state = if play.progress.defeated.contains(&id) {
+                " (defeated)"
+            } else {
+                ""
+            };
+            text.push(Text::raw("\n    Occupant: "));
+            text.push(Text::styled(
+                format!("{}{}", c.name, state),
+                self.theme.team(c.team.raw()),
+            ));
+        }
+        if our_turn {
+            let action = match play.action() {
+                Some(WireAction::Move(_)) => "move there",
+                Some(WireAction::Attack(_)) => "attack",
+                Some(WireAction::EndTurn) => "end the turn",
+                None => "nothing",
+            };
+            text.push(Text::raw(format!("\n    Confirm:  {}", action)));
+        }
+
+        if !play.progress.defeated.is_empty() {
+            text.push(Text::raw("\n\n    Defeated:"));
+            for c in play
+                .progress
+                .defeated
+                .iter()
+                .filter_map(|id| characters.get(*id))
+            {
+                text.push(Text::raw("\n      "));
+                text.push(Text::styled(c.name.clone(), self.theme.team(c.team.raw())));
             }
         }
 
-        Block::default()
-            .title("PLAYING THE GAME ASODUHASUOB")
-            .borders(Borders::ALL)
-            .render(self.f, self.chunks[1]);
+        self.panel(PLAY_GAME_BLOCK_TITLE, &text);
+    }
+
+    fn game_over(mut self, s: &GameOverData) {
+        let g = s.curr();
+        let winner = g
+            .map
+            .teams
+            .get(g.winner.raw())
+            .map_or("?", |(name, _)| name.as_str());
+        let outcome = if g.won { "You won!" } else { "You lost." };
+        let text = [
+            Text::styled(format!("    {}", outcome), self.theme.title),
+            Text::raw("\n    Winning team: "),
+            Text::styled(winner.to_owned(), self.theme.team(g.winner.raw())),
+            Text::raw("\n\n    Press enter to go back to the start."),
+        ];
+        FormatMap(g.map, None, self.theme).render(self.f, self.chunks[0]);
+        self.panel(GAME_OVER_BLOCK_TITLE, &text);
     }
 }
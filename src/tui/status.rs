@@ -0,0 +1,71 @@
+use crate::tui::theme::Theme;
+use ::tui::backend::CrosstermBackend;
+use ::tui::buffer::Buffer;
+use ::tui::layout::Rect;
+use ::tui::widgets::Widget;
+use ::tui::Terminal;
+use pewcraft_frontend::api::{ConnectionStatus, StatusObserver};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Shows whether the server can be reached, at the right of the hint bar
+pub(super) struct ConnectionIndicator<'a>(pub(super) ConnectionStatus, pub(super) &'a Theme);
+
+impl<'a> ConnectionIndicator<'a> {
+    pub(super) const WIDTH: u16 = 16;
+}
+
+impl<'a> Widget for ConnectionIndicator<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let theme = self.1;
+        let (text, style) = match self.0 {
+            ConnectionStatus::Connected => ("● connected".to_owned(), theme.ok),
+            ConnectionStatus::Retrying(attempt) => {
+                (format!("● retrying ({})", attempt), theme.warning)
+            }
+            ConnectionStatus::Offline => ("● offline".to_owned(), theme.error),
+        };
+        buf.set_stringn(area.x, area.y, &text, area.width as usize, style);
+    }
+}
+
+// Draws the indicator as soon as the status changes, while a request blocks the UI thread
+// (e.g. between the retries of a request), instead of waiting for the next frame
+#[derive(Clone, Default)]
+pub(super) struct LiveIndicator {
+    // where the last frame drew the indicator
+    area: Arc<Mutex<Rect>>,
+    // the screen shows something the last frame did not draw
+    drawn: Arc<AtomicBool>,
+}
+
+impl LiveIndicator {
+    pub(super) fn set_area(&self, area: Rect) {
+        *self.area.lock().unwrap() = area;
+    }
+
+    // Whether the next frame must draw the whole screen again
+    pub(super) fn take_drawn(&self) -> bool {
+        self.drawn.swap(false, Ordering::SeqCst)
+    }
+
+    pub(super) fn observer(&self, theme: Theme) -> StatusObserver {
+        let live = self.clone();
+        let ui = thread::current().id();
+        Box::new(move |status| {
+            // e.g. bots playing in the background must not draw over the frame being drawn
+            if thread::current().id() != ui {
+                return;
+            }
+            let area = *live.area.lock().unwrap();
+            // a new terminal only writes the cells drawn on it, the rest of the screen is kept
+            if let Ok(mut terminal) = Terminal::new(CrosstermBackend::new(io::stdout())) {
+                let drawn =
+                    terminal.draw(|mut f| ConnectionIndicator(status, &theme).render(&mut f, area));
+                live.drawn.store(drawn.is_ok(), Ordering::SeqCst);
+            }
+        })
+    }
+}
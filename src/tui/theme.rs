@@ -0,0 +1,263 @@
+use crate::tui::glyphs::{GlyphSet, Glyphs};
+use ::tui::style::{Color, Modifier, Style};
+use pewcraft_frontend::config;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+
+const THEME_FILE: &str = "theme.json";
+
+// Every style the renderers use, so that none of them hardcodes a color
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub(super) text: Style,
+    pub(super) title: Style,
+    pub(super) border: Style,
+    // the current option of a menu
+    pub(super) selection: Style,
+    // secondary text, e.g. the hint bar
+    pub(super) hint: Style,
+    pub(super) ok: Style,
+    pub(super) warning: Style,
+    pub(super) error: Style,
+    // the n-th team of a map uses the n-th color, cycling when there are more teams
+    teams: Vec<Color>,
+    // by cell type, i.e. the name of the Cell variant ("Wall", ...)
+    cells: BTreeMap<String, Color>,
+    cell: Style,
+    pub(super) glyphs: Glyphs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Dark,
+    Light,
+    HighContrast,
+    // no color at all, only bold/reversed text: used when NO_COLOR is set
+    Monochrome,
+}
+
+impl Preset {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Preset::Dark),
+            "light" => Some(Preset::Light),
+            "high_contrast" => Some(Preset::HighContrast),
+            "monochrome" => Some(Preset::Monochrome),
+            _ => None,
+        }
+    }
+}
+
+// Content of the theme file, e.g.:
+// {
+//   "theme": "light",
+//   "teams": ["red", "#0087ff"],
+//   "cells": { "Wall": "darkgray", "Water": "blue" },
+//   "glyphs": "rounded"
+// }
+// The glyph set is one of "double", "single", "rounded" and "ascii", or "auto" to detect it.
+#[derive(Debug, Default, Deserialize)]
+struct ThemeFile {
+    theme: Option<String>,
+    #[serde(default)]
+    teams: Vec<String>,
+    #[serde(default)]
+    cells: BTreeMap<String, String>,
+    glyphs: Option<String>,
+}
+
+fn parse_color(s: &str) -> Result<Color, String> {
+    let color = match s.to_lowercase().as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.len() == 7 && hex.starts_with('#') => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+            match (channel(1), channel(3), channel(5)) {
+                (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                _ => return Err(format!("invalid color '{}'", s)),
+            }
+        }
+        _ => return Err(format!("unknown color '{}'", s)),
+    };
+    Ok(color)
+}
+
+impl Theme {
+    pub fn from_preset(preset: Preset) -> Self {
+        let glyphs = Glyphs::from_set(GlyphSet::Double);
+        let bold = Style::default().modifier(Modifier::BOLD);
+        match preset {
+            Preset::Dark => Theme {
+                text: Style::default(),
+                title: bold.fg(Color::Cyan),
+                border: Style::default().fg(Color::Gray),
+                selection: Style::default().fg(Color::Black).bg(Color::Yellow),
+                hint: Style::default().fg(Color::DarkGray),
+                ok: Style::default().fg(Color::Green),
+                warning: Style::default().fg(Color::Yellow),
+                error: Style::default().fg(Color::Red),
+                teams: vec![
+                    Color::Red,
+                    Color::Blue,
+                    Color::Green,
+                    Color::Magenta,
+                    Color::Cyan,
+                    Color::Yellow,
+                ],
+                cells: BTreeMap::new(),
+                cell: Style::default().fg(Color::DarkGray),
+                glyphs,
+            },
+            Preset::Light => Theme {
+                text: Style::default().fg(Color::Black),
+                title: bold.fg(Color::Blue),
+                border: Style::default().fg(Color::DarkGray),
+                selection: Style::default().fg(Color::White).bg(Color::Blue),
+                hint: Style::default().fg(Color::DarkGray),
+                ok: Style::default().fg(Color::Green),
+                // yellow is unreadable on a light background
+                warning: Style::default().fg(Color::Rgb(0xaf, 0x5f, 0x00)),
+                error: Style::default().fg(Color::Red),
+                teams: vec![
+                    Color::Red,
+                    Color::Blue,
+                    Color::Green,
+                    Color::Magenta,
+                    Color::Cyan,
+                    Color::Black,
+                ],
+                cells: BTreeMap::new(),
+                cell: Style::default().fg(Color::Gray),
+                glyphs,
+            },
+            Preset::HighContrast => Theme {
+                text: Style::default().fg(Color::White).bg(Color::Black),
+                title: bold
+                    .fg(Color::White)
+                    .bg(Color::Black)
+                    .modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                border: Style::default().fg(Color::White).bg(Color::Black),
+                selection: bold.fg(Color::Black).bg(Color::White),
+                hint: Style::default().fg(Color::White).bg(Color::Black),
+                ok: bold.fg(Color::LightGreen).bg(Color::Black),
+                warning: bold.fg(Color::LightYellow).bg(Color::Black),
+                error: bold.fg(Color::LightRed).bg(Color::Black),
+                teams: vec![
+                    Color::LightRed,
+                    Color::LightBlue,
+                    Color::LightGreen,
+                    Color::LightYellow,
+                    Color::LightMagenta,
+                    Color::LightCyan,
+                ],
+                cells: BTreeMap::new(),
+                cell: Style::default().fg(Color::White).bg(Color::Black),
+                glyphs,
+            },
+            Preset::Monochrome => Theme {
+                text: Style::default(),
+                title: bold,
+                border: Style::default(),
+                selection: Style::default().modifier(Modifier::REVERSED),
+                hint: Style::default().modifier(Modifier::DIM),
+                ok: Style::default(),
+                warning: bold,
+                error: bold,
+                teams: Vec::new(),
+                cells: BTreeMap::new(),
+                cell: Style::default(),
+                glyphs,
+            },
+        }
+    }
+
+    // Loads the theme from the config directory. NO_COLOR (https://no-color.org) wins over its
+    // colors, and `glyphs` (chosen on the command line) over its glyph set.
+    // Problems do not prevent starting, they are returned so that they can be reported.
+    pub fn load(glyphs: Option<GlyphSet>) -> (Self, Vec<String>) {
+        let (file, mut problems) = match config::read::<ThemeFile>(THEME_FILE) {
+            Ok(file) => (file.unwrap_or_default(), Vec::new()),
+            Err(e) => (ThemeFile::default(), vec![e]),
+        };
+
+        let configured = match file.glyphs.as_deref() {
+            None | Some("auto") => None,
+            Some(name) => GlyphSet::from_name(name).or_else(|| {
+                problems.push(format!("unknown glyph set '{}'", name));
+                None
+            }),
+        };
+        let glyphs = glyphs.or(configured).unwrap_or_else(GlyphSet::detect);
+
+        let mut theme = if env::var_os("NO_COLOR").map_or(false, |v| !v.is_empty()) {
+            Theme::from_preset(Preset::Monochrome)
+        } else {
+            Theme::from_file(file, &mut problems)
+        };
+        theme.glyphs = Glyphs::from_set(glyphs);
+        (theme, problems)
+    }
+
+    fn from_file(file: ThemeFile, problems: &mut Vec<String>) -> Self {
+        let preset = match file.theme.as_deref().map(|t| (t, Preset::from_name(t))) {
+            None => Preset::Dark,
+            Some((_, Some(preset))) => preset,
+            Some((name, None)) => {
+                problems.push(format!("unknown theme '{}', using 'dark'", name));
+                Preset::Dark
+            }
+        };
+        let mut theme = Theme::from_preset(preset);
+
+        let teams: Vec<Color> = file
+            .teams
+            .iter()
+            .filter_map(|color| parse_color(color).map_err(|e| problems.push(e)).ok())
+            .collect();
+        if !teams.is_empty() {
+            theme.teams = teams;
+        }
+        for (cell, color) in file.cells {
+            match parse_color(&color) {
+                Ok(color) => {
+                    theme.cells.insert(cell, color);
+                }
+                Err(e) => problems.push(format!("cell '{}': {}", cell, e)),
+            }
+        }
+
+        theme
+    }
+
+    pub(super) fn team(&self, index: usize) -> Style {
+        if self.teams.is_empty() {
+            self.text.modifier(Modifier::BOLD)
+        } else {
+            self.text.fg(self.teams[index % self.teams.len()])
+        }
+    }
+
+    // `cell_type` is the name of the Cell variant
+    pub(super) fn cell(&self, cell_type: &str) -> Style {
+        match self.cells.get(cell_type) {
+            Some(color) => self.cell.fg(*color),
+            None => self.cell,
+        }
+    }
+}
@@ -1,37 +1,81 @@
-use crate::state::{Event, GlobalState, State};
+use crate::tui::clipboard::Clipboard;
+use crate::tui::encyclopedia::{self, Encyclopedia};
 use crate::tui::input::{self, TuiEvent};
-use crate::tui::render::Renderer;
-use clipboard::{ClipboardContext, ClipboardProvider};
+use crate::tui::keymap::Keymap;
+use crate::tui::overlay::Overlays;
+use crate::tui::panel::Scroll;
+use crate::tui::panes::Panes;
+use crate::tui::render::{self, ClickTarget, Prefs, Renderer};
+use crate::tui::status::LiveIndicator;
+use crate::tui::theme::Theme;
 use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    Command,
 };
 use log::{debug, info};
 use pewcraft_common::game::GameDefinition;
+use pewcraft_frontend::api::Endpoint;
+use pewcraft_frontend::state::{Event, ExpectedEvent, GlobalState, State};
+use std::fs::OpenOptions;
 use std::io::{Stdin, Stdout, StdoutLock, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use tui::backend::CrosstermBackend;
 use tui::Terminal;
 
 pub struct Tui<'a> {
     game_definition: &'a GameDefinition,
+    endpoint: &'a Endpoint,
+    keymap: Keymap,
+    theme: Theme,
+    panes: Panes,
+    scroll: Scroll,
+    overlays: Overlays,
+    live_indicator: LiveIndicator,
     //stdin: Bytes<StdinLock<'a>>,
     stdout: Terminal<CrosstermBackend<StdoutLock<'a>>>,
-    clipboard: ClipboardContext,
+    clipboard: Clipboard,
+    // read while grouping a paste, handled on the next frame
+    pending: Option<input::RawEvent>,
 }
 
+// Whether the terminal was taken over, only then is there anything to restore: commands and the
+// line-based front ends must not get escape sequences in their output
+static TAKEN_OVER: AtomicBool = AtomicBool::new(false);
+
 impl<'a> Tui<'a> {
-    pub fn new(game_definition: &'a GameDefinition, _: &'a Stdin, stdout: &'a mut Stdout) -> Self {
-        enable_raw_mode().unwrap();
-        execute!(stdout, EnterAlternateScreen).unwrap();
+    pub fn new(
+        game_definition: &'a GameDefinition,
+        endpoint: &'a Endpoint,
+        keymap: Keymap,
+        theme: Theme,
+        _: &'a Stdin,
+        stdout: &'a mut Stdout,
+    ) -> Self {
+        take_over_terminal();
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture).unwrap();
         let backend = CrosstermBackend::new(stdout.lock());
         //let stdin = stdin.lock().bytes();
         let stdout = Terminal::new(backend).unwrap();
-        let clipboard = ClipboardProvider::new().unwrap();
+        let clipboard = Clipboard::new();
+        let live_indicator = LiveIndicator::default();
+        endpoint.observe_status(Some(live_indicator.observer(theme.clone())));
         Tui {
             game_definition,
+            endpoint,
+            keymap,
+            theme,
+            panes: Panes::load(),
+            scroll: Scroll::default(),
+            overlays: Overlays::new(),
+            live_indicator,
             //stdin,
             stdout,
             clipboard,
+            pending: None,
         }
     }
 
@@ -39,26 +83,159 @@ impl<'a> Tui<'a> {
         debug!("tui.rs:render");
 
         self.stdout.hide_cursor().unwrap();
+        if self.live_indicator.take_drawn() {
+            self.stdout.clear().unwrap();
+        }
+        let area = self.stdout.size().unwrap();
+        self.live_indicator.set_area(self.panes.split(area)[3]);
 
         let g = self.game_definition;
+        let prefs = Prefs {
+            keymap: &self.keymap,
+            theme: &self.theme,
+            panes: &self.panes,
+        };
+        let scroll = &self.scroll;
+        let overlays = &self.overlays;
+        let status = self.endpoint.status();
+        let start = Instant::now();
         self.stdout
-            .draw(|mut f| Renderer::render(&mut f, s, g))
+            .draw(|mut f| Renderer::render(&mut f, s, g, prefs, scroll, overlays, status))
             .unwrap();
+        self.overlays.frame_time = start.elapsed();
 
         debug!("Current state: {:?}", s);
 
-        match input::get(s.expected_event()) {
+        let expected_event = s.expected_event();
+        let text_input = matches!(expected_event, ExpectedEvent::Char);
+        let event = input::get(expected_event, &self.keymap, &mut self.pending);
+
+        // the help overlay is modal: only closing it does something
+        if self.overlays.help {
+            return match event {
+                TuiEvent::ToggleHelp | TuiEvent::StateEvent(Event::Cancel) => {
+                    self.overlays.help = false;
+                    Event::Other
+                }
+                TuiEvent::StateEvent(Event::Timeout) => Event::Timeout,
+                _ => Event::Other,
+            };
+        }
+
+        // so are the encyclopedia and the character sheet
+        if let Some(e) = &mut self.overlays.encyclopedia {
+            let count = encyclopedia::class_count(g);
+            match event {
+                TuiEvent::ToggleClasses | TuiEvent::StateEvent(Event::Cancel) => {
+                    self.overlays.encyclopedia = None
+                }
+                TuiEvent::StateEvent(Event::Left) | TuiEvent::StateEvent(Event::Up) => {
+                    e.previous(count)
+                }
+                TuiEvent::StateEvent(Event::Right) | TuiEvent::StateEvent(Event::Down) => {
+                    e.next(count)
+                }
+                TuiEvent::StateEvent(Event::Confirm) => e.toggle_pin(),
+                TuiEvent::PageUp => self.overlays.scroll.page_up(),
+                TuiEvent::PageDown => self.overlays.scroll.page_down(),
+                TuiEvent::StateEvent(Event::Timeout) => return Event::Timeout,
+                _ => {}
+            }
+            return Event::Other;
+        }
+        if self.overlays.sheet {
+            match event {
+                TuiEvent::ToggleSheet | TuiEvent::StateEvent(Event::Cancel) => {
+                    self.overlays.sheet = false
+                }
+                TuiEvent::PageUp => self.overlays.scroll.page_up(),
+                TuiEvent::PageDown => self.overlays.scroll.page_down(),
+                TuiEvent::StateEvent(Event::Timeout) => return Event::Timeout,
+                _ => {}
+            }
+            return Event::Other;
+        }
+
+        match event {
             TuiEvent::StateEvent(e) => e,
-            // TODO
+            TuiEvent::ToggleHelp => {
+                self.overlays.help = true;
+                Event::Other
+            }
+            TuiEvent::ToggleLog => {
+                self.overlays.log_console.toggle();
+                Event::Other
+            }
+            TuiEvent::CycleLogLevel => {
+                self.overlays.log_console.cycle_level();
+                Event::Other
+            }
+            TuiEvent::CycleLogModule => {
+                self.overlays.log_console.cycle_module();
+                Event::Other
+            }
+            TuiEvent::ToggleDebug => {
+                self.overlays.debug = !self.overlays.debug;
+                Event::Other
+            }
+            TuiEvent::CycleLayout => {
+                self.panes.cycle_arrangement();
+                Event::Other
+            }
+            TuiEvent::TogglePanel => {
+                self.panes.toggle_panel();
+                Event::Other
+            }
+            TuiEvent::GrowPanel => {
+                self.panes.grow_panel();
+                Event::Other
+            }
+            TuiEvent::ShrinkPanel => {
+                self.panes.shrink_panel();
+                Event::Other
+            }
+            TuiEvent::ToggleClasses => {
+                self.overlays.encyclopedia = Some(Encyclopedia::new());
+                Event::Other
+            }
+            // only while playing, there is no character before
+            TuiEvent::ToggleSheet => {
+                self.overlays.sheet = matches!(s, GlobalState::PlayGame(_));
+                Event::Other
+            }
+            TuiEvent::PageUp => {
+                self.scroll.page_up();
+                Event::Other
+            }
+            TuiEvent::PageDown => {
+                self.scroll.page_down();
+                Event::Other
+            }
             TuiEvent::CopyClipboard => {
                 if let Some(string) = s.get_game_id() {
-                    self.clipboard.set_contents(string).unwrap();
+                    self.clipboard.set(self.stdout.backend_mut(), string);
                 }
                 Event::Other
             }
-            // TODO
-            TuiEvent::PasteClipboard => {
-                Event::PrintableString(self.clipboard.get_contents().unwrap())
+            TuiEvent::PasteClipboard if text_input => match self.clipboard.get() {
+                // only the first line makes sense in our single line fields
+                Some(string) => match string.lines().next() {
+                    Some(line) => Event::PrintableString(line.trim().to_owned()),
+                    None => Event::Other,
+                },
+                None => Event::Other,
+            },
+            TuiEvent::PasteClipboard => Event::Other,
+            // what is clicked on may be hidden by an overlay
+            TuiEvent::Click(..) if self.overlays.any_visible() => Event::Other,
+            TuiEvent::Click(column, row) => {
+                let area = self.stdout.size().unwrap();
+                let (panes, scroll) = (&self.panes, &self.scroll);
+                match render::click_target(area, s, g, panes, scroll, column, row) {
+                    Some(ClickTarget::Option(index)) => Event::Choose(index),
+                    Some(ClickTarget::Cell(cell)) => Event::Cell(cell),
+                    None => Event::Other,
+                }
             }
         }
     }
@@ -66,7 +243,34 @@ impl<'a> Tui<'a> {
 
 impl<'a> Drop for Tui<'a> {
     fn drop(&mut self) {
-        disable_raw_mode().unwrap();
-        execute!(self.stdout.backend_mut(), LeaveAlternateScreen).unwrap();
+        self.endpoint.observe_status(None);
+        restore_terminal();
+    }
+}
+
+// Raw mode, until `restore_terminal` is called
+pub(super) fn take_over_terminal() {
+    TAKEN_OVER.store(true, Ordering::SeqCst);
+    enable_raw_mode().unwrap();
+}
+
+// Gives the terminal back in the state we found it, if it was taken over.
+// Also called from the panic hook, so errors are ignored: there is nothing left to do about them.
+// The Tui keeps stdout locked while it lives, and the hook may run on another thread (a bot's):
+// the escape sequences go straight to the terminal, or to stderr when there is none.
+pub fn restore_terminal() {
+    if !TAKEN_OVER.swap(false, Ordering::SeqCst) {
+        return;
     }
+    let _ = disable_raw_mode();
+    let sequences = format!(
+        "{}{}{}",
+        DisableMouseCapture.ansi_code(),
+        LeaveAlternateScreen.ansi_code(),
+        Show.ansi_code()
+    );
+    let _ = match OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(mut tty) => tty.write_all(sequences.as_bytes()),
+        Err(_) => std::io::stderr().write_all(sequences.as_bytes()),
+    };
 }
@@ -0,0 +1,233 @@
+use pewcraft_common::game::{Cell, GameDefinition, GameMap, Id};
+use std::fmt;
+
+// A problem in the game definition sent by the server, which would make the client misbehave
+// or crash later on (the renderers assume the definition is consistent)
+#[derive(Debug)]
+pub struct Problem {
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+pub fn validate(game: &GameDefinition) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut problem =
+        |location: String, message: String| problems.push(Problem { location, message });
+
+    entries(
+        "class",
+        game.classes.ids(),
+        |id| game.classes.get(id),
+        &mut problem,
+    );
+    for map in entries("map", game.maps.ids(), |id| game.maps.get(id), &mut problem) {
+        validate_map(map, &mut problem);
+    }
+
+    problems
+}
+
+// The entries behind the ids, there must be at least one and each id must have its entry
+fn entries<'g, T, G, F>(kind: &str, ids: Vec<Id<T>>, get: G, problem: &mut F) -> Vec<&'g T>
+where
+    G: Fn(Id<T>) -> Option<&'g T>,
+    F: FnMut(String, String),
+{
+    if ids.is_empty() {
+        problem(
+            format!("{}s", kind),
+            format!("there is no {} to choose from", kind),
+        );
+    }
+    let mut entries = Vec::new();
+    for id in ids {
+        match get(id) {
+            Some(entry) => entries.push(entry),
+            None => problem(
+                format!("{} {}", kind, id.raw()),
+                "listed but missing".to_owned(),
+            ),
+        }
+    }
+    entries
+}
+
+fn validate_map<F: FnMut(String, String)>(map: &GameMap, problem: &mut F) {
+    let location = format!("map '{}'", map.name);
+
+    if map.width == 0 || map.height == 0 {
+        problem(
+            location.clone(),
+            format!("invalid dimensions {}x{}", map.width, map.height),
+        );
+    }
+    let cells = map.width * map.height;
+    if map.data.len() != cells {
+        problem(
+            location.clone(),
+            format!(
+                "{}x{} map but {} cells of data",
+                map.width,
+                map.height,
+                map.data.len()
+            ),
+        );
+    }
+
+    if map.teams.is_empty() {
+        problem(location.clone(), "no team can play on it".to_owned());
+    }
+
+    let mut spawns: Vec<(Id<Cell>, &str)> = Vec::new();
+    for (name, positions) in map.teams.iter() {
+        let team_location = format!("{}, team '{}'", location, name);
+        if positions.is_empty() {
+            problem(team_location.clone(), "no spawn position".to_owned());
+        }
+
+        for position in positions {
+            if position.raw() >= cells {
+                problem(
+                    team_location.clone(),
+                    format!(
+                        "spawn position {} is out of the {}x{} map",
+                        position.raw(),
+                        map.width,
+                        map.height
+                    ),
+                );
+            }
+            match spawns.iter().find(|(p, _)| p == position) {
+                Some((_, other)) => problem(
+                    team_location.clone(),
+                    format!(
+                        "spawn position {} is also used by team '{}'",
+                        position.raw(),
+                        other
+                    ),
+                ),
+                None => spawns.push((*position, name.as_str())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn duel() -> serde_json::Value {
+        json!({
+            "name": "Duel",
+            "width": 3,
+            "height": 2,
+            "data": ["Floor", "Floor", "Wall", "Floor", "Floor", "Floor"],
+            "teams": [["Red", [0]], ["Blue", [5]]]
+        })
+    }
+
+    fn definition(maps: serde_json::Value, classes: serde_json::Value) -> GameDefinition {
+        serde_json::from_value(json!({ "maps": maps, "classes": classes })).unwrap()
+    }
+
+    // The messages about the duel map, once changed by `edit`
+    fn map_problems(edit: impl FnOnce(&mut serde_json::Value)) -> Vec<String> {
+        let mut map = duel();
+        edit(&mut map);
+        let map: GameMap = serde_json::from_value(map).unwrap();
+        let mut problems = Vec::new();
+        validate_map(&map, &mut |_, message| problems.push(message));
+        problems
+    }
+
+    #[test]
+    fn a_consistent_definition_has_no_problem() {
+        let classes = json!([{ "name": "Warrior", "description": "Hits hard" }]);
+        let problems = validate(&definition(json!([duel()]), classes));
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+
+    #[test]
+    fn there_must_be_a_class() {
+        let problems = validate(&definition(json!([duel()]), json!([])));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].location, "classes");
+    }
+
+    #[test]
+    fn there_must_be_a_map() {
+        let classes = json!([{ "name": "Warrior", "description": "Hits hard" }]);
+        let problems = validate(&definition(json!([]), classes));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].location, "maps");
+    }
+
+    #[test]
+    fn listed_entries_must_exist() {
+        let names = ["first", "third"];
+        let ids = vec![Id::<&str>::new(0), Id::new(1), Id::new(2)];
+        let mut problems = Vec::new();
+        let found = entries(
+            "name",
+            ids,
+            |id| match id.raw() {
+                0 => Some(&names[0]),
+                2 => Some(&names[1]),
+                _ => None,
+            },
+            &mut |location, _| problems.push(location),
+        );
+        assert_eq!(found, vec![&"first", &"third"]);
+        assert_eq!(problems, vec!["name 1"]);
+    }
+
+    #[test]
+    fn dimensions_must_not_be_zero() {
+        let problems = map_problems(|map| {
+            map["width"] = json!(0);
+            map["data"] = json!([]);
+        });
+        // the spawn positions are then out of the map as well
+        assert_eq!(problems[0], "invalid dimensions 0x2");
+    }
+
+    #[test]
+    fn data_must_fill_the_map() {
+        let problems = map_problems(|map| map["height"] = json!(3));
+        assert_eq!(problems, vec!["3x3 map but 6 cells of data"]);
+    }
+
+    #[test]
+    fn a_team_must_be_able_to_play() {
+        let problems = map_problems(|map| map["teams"] = json!([]));
+        assert_eq!(problems, vec!["no team can play on it"]);
+    }
+
+    #[test]
+    fn teams_must_have_a_spawn_position() {
+        let problems = map_problems(|map| map["teams"][1] = json!(["Blue", []]));
+        assert_eq!(problems, vec!["no spawn position"]);
+    }
+
+    #[test]
+    fn spawn_positions_must_be_on_the_map() {
+        let problems = map_problems(|map| map["teams"][1] = json!(["Blue", [6]]));
+        assert_eq!(problems, vec!["spawn position 6 is out of the 3x2 map"]);
+    }
+
+    #[test]
+    fn spawn_positions_must_not_be_shared() {
+        let problems = map_problems(|map| map["teams"][1] = json!(["Blue", [5, 0]]));
+        assert_eq!(
+            problems,
+            vec!["spawn position 0 is also used by team 'Red'"]
+        );
+    }
+}